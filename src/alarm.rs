@@ -0,0 +1,351 @@
+use crate::app::draw_flash;
+use crate::renderer::{Color, Frame, COLS, MAX_BRIGHTNESS, MIN_BRIGHTNESS, ROWS};
+use crate::schedule::{deserialize_days, deserialize_time, serialize_days, serialize_time};
+use crate::state::RenderState;
+use chrono::{Datelike, NaiveDateTime, NaiveTime, Timelike, Weekday};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Alarm {
+    pub name: String,
+    #[serde(
+        deserialize_with = "deserialize_time",
+        serialize_with = "serialize_time"
+    )]
+    pub time: NaiveTime,
+    // empty for every day
+    #[serde(
+        default,
+        deserialize_with = "deserialize_days",
+        serialize_with = "serialize_days"
+    )]
+    pub days: Vec<Weekday>,
+    #[serde(default)]
+    pub label: Option<String>,
+    // kept as text to be saved as it was given
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+impl Alarm {
+    fn is_due(&self, now: NaiveDateTime) -> bool {
+        self.enabled
+            && self.time.hour() == now.hour()
+            && self.time.minute() == now.minute()
+            && (self.days.is_empty() || self.days.contains(&now.weekday()))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum AlarmCommand {
+    Set(Alarm),
+    Delete { name: String },
+    Dismiss,
+    Snooze,
+}
+
+impl AlarmCommand {
+    // JSON, or plain text "dismiss" or "snooze"
+    pub fn parse(value: &str) -> Result<AlarmCommand, String> {
+        match value.trim() {
+            "dismiss" => Ok(AlarmCommand::Dismiss),
+            "snooze" => Ok(AlarmCommand::Snooze),
+            value => serde_json::from_str(value).map_err(|err| err.to_string()),
+        }
+    }
+}
+
+fn default_sunrise() -> u64 {
+    5
+}
+
+fn default_snooze() -> u64 {
+    9
+}
+
+fn default_timeout() -> u64 {
+    30
+}
+
+// all in minutes
+#[derive(Clone, Deserialize)]
+pub struct AlarmConfig {
+    // the brightness ramp before the alarm flashes, 0 to flash right away
+    #[serde(default = "default_sunrise")]
+    pub sunrise: u64,
+    #[serde(default = "default_snooze")]
+    pub snooze: u64,
+    // an alarm nobody dismisses stops after this
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+}
+
+impl Default for AlarmConfig {
+    fn default() -> AlarmConfig {
+        AlarmConfig {
+            sunrise: default_sunrise(),
+            snooze: default_snooze(),
+            timeout: default_timeout(),
+        }
+    }
+}
+
+pub struct Ringing {
+    alarm: Alarm,
+    since: Instant,
+    // a snoozed alarm flashes again without the sunrise
+    sunrise: Duration,
+}
+
+impl Ringing {
+    fn sunrise_progress(&self) -> Option<f32> {
+        let elapsed = self.since.elapsed();
+        if elapsed < self.sunrise {
+            Some(elapsed.as_secs_f32() / self.sunrise.as_secs_f32())
+        } else {
+            None
+        }
+    }
+
+    // the panel brightness during the sunrise
+    pub fn brightness(&self) -> Option<f32> {
+        self.sunrise_progress()
+            .map(|progress| MIN_BRIGHTNESS + (MAX_BRIGHTNESS - MIN_BRIGHTNESS) * progress)
+    }
+
+    pub fn render(&self, state: &RenderState, frame: &mut Frame) {
+        let color = self
+            .alarm
+            .color
+            .as_ref()
+            .and_then(|color| color.parse::<Color>().ok());
+
+        match self.sunrise_progress() {
+            // deep red through orange to a warm white
+            Some(progress) => {
+                let sunrise = color.unwrap_or_else(|| {
+                    let channel = |from: f32, to: f32| (from + (to - from) * progress) as u8;
+                    Color::from_rgb8(255, channel(20.0, 200.0), channel(0.0, 120.0))
+                });
+                frame.fill_rect(&sunrise, 0, 0, COLS, ROWS);
+            }
+            None => {
+                let text = match &self.alarm.label {
+                    Some(label) => label.to_uppercase(),
+                    None => self.alarm.time.format("%H:%M").to_string(),
+                };
+                draw_flash(
+                    state,
+                    frame,
+                    self.since.elapsed() - self.sunrise,
+                    &text,
+                    &color.unwrap_or_else(|| Color::from_rgb8(255, 0, 0)),
+                );
+            }
+        }
+    }
+}
+
+// what happened to an alarm, published as an event
+pub struct AlarmEvent {
+    pub name: String,
+    pub event: &'static str,
+}
+
+pub struct Alarms {
+    alarms: Vec<Alarm>,
+    path: Option<PathBuf>,
+    config: AlarmConfig,
+    // the minute last checked, so an alarm fires once
+    checked: Option<NaiveDateTime>,
+    pub ringing: Option<Ringing>,
+    snoozed: Option<(Instant, Alarm)>,
+}
+
+impl Alarms {
+    // alarms are saved to `path` on every change
+    pub fn load(path: Option<PathBuf>, config: AlarmConfig) -> Alarms {
+        let alarms = match &path {
+            Some(path) if path.exists() => std::fs::read_to_string(path)
+                .map_err(|err| err.to_string())
+                .and_then(|content| serde_json::from_str(&content).map_err(|err| err.to_string()))
+                .unwrap_or_else(|err| {
+                    println!("Cannot load alarms {}: {}", path.display(), err);
+                    Vec::new()
+                }),
+            _ => Vec::new(),
+        };
+
+        Alarms {
+            alarms,
+            path,
+            config,
+            checked: None,
+            ringing: None,
+            snoozed: None,
+        }
+    }
+
+    // written next to the file and renamed over it, so a failed write keeps the old alarms
+    fn save(&self, alarms: &[Alarm]) -> Result<(), String> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let content = serde_json::to_string_pretty(alarms).map_err(|err| err.to_string())?;
+        let mut temp = path.clone().into_os_string();
+        temp.push(".tmp");
+        std::fs::write(&temp, content)
+            .and_then(|_| std::fs::rename(&temp, path))
+            .map_err(|err| format!("cannot save alarms {}: {}", path.display(), err))
+    }
+
+    // the alarms change only once they are saved
+    fn replace(&mut self, alarms: Vec<Alarm>) -> Result<(), String> {
+        self.save(&alarms)?;
+        self.alarms = alarms;
+        Ok(())
+    }
+
+    pub fn list(&self) -> String {
+        serde_json::to_string(&self.alarms).unwrap_or_default()
+    }
+
+    fn ring(&mut self, alarm: Alarm, sunrise: Duration) {
+        self.ringing = Some(Ringing {
+            alarm,
+            since: Instant::now(),
+            sunrise,
+        });
+    }
+
+    pub fn command(&mut self, command: AlarmCommand) -> Result<Option<AlarmEvent>, String> {
+        match command {
+            AlarmCommand::Set(alarm) => {
+                if let Some(color) = &alarm.color {
+                    color.parse::<Color>().map_err(|err| err.to_string())?;
+                }
+                let mut alarms = self.alarms.clone();
+                alarms.retain(|existing| existing.name != alarm.name);
+                alarms.push(alarm);
+                self.replace(alarms)?;
+                Ok(None)
+            }
+            AlarmCommand::Delete { name } => {
+                let mut alarms = self.alarms.clone();
+                alarms.retain(|alarm| alarm.name != name);
+                if alarms.len() == self.alarms.len() {
+                    return Err(format!("unknown alarm '{}'", name));
+                }
+                self.replace(alarms)?;
+                Ok(None)
+            }
+            AlarmCommand::Dismiss => Ok(self.dismiss()),
+            AlarmCommand::Snooze => Ok(self.snooze()),
+        }
+    }
+
+    pub fn dismiss(&mut self) -> Option<AlarmEvent> {
+        self.snoozed = None;
+        self.ringing.take().map(|ringing| AlarmEvent {
+            name: ringing.alarm.name,
+            event: "dismissed",
+        })
+    }
+
+    pub fn snooze(&mut self) -> Option<AlarmEvent> {
+        let ringing = self.ringing.take()?;
+        let until = Instant::now() + Duration::from_secs(self.config.snooze * 60);
+        let name = ringing.alarm.name.clone();
+        self.snoozed = Some((until, ringing.alarm));
+        Some(AlarmEvent {
+            name,
+            event: "snoozed",
+        })
+    }
+
+    // checks the alarms once a minute and brings back snoozed ones
+    pub fn update(&mut self, now: NaiveDateTime) -> Vec<AlarmEvent> {
+        let mut events = Vec::new();
+
+        let timeout = Duration::from_secs(self.config.timeout * 60);
+        if let Some(ringing) = &self.ringing {
+            if ringing.since.elapsed() >= ringing.sunrise + timeout {
+                events.push(AlarmEvent {
+                    name: ringing.alarm.name.clone(),
+                    event: "timeout",
+                });
+                self.ringing = None;
+            }
+        }
+
+        if let Some((until, _)) = &self.snoozed {
+            if Instant::now() >= *until {
+                if let Some((_, alarm)) = self.snoozed.take() {
+                    events.push(AlarmEvent {
+                        name: alarm.name.clone(),
+                        event: "fired",
+                    });
+                    self.ring(alarm, Duration::from_secs(0));
+                }
+            }
+        }
+
+        let minute = now.date().and_hms(now.hour(), now.minute(), 0);
+        if self.checked == Some(minute) {
+            return events;
+        }
+        self.checked = Some(minute);
+
+        if let Some(alarm) = self.alarms.iter().find(|alarm| alarm.is_due(now)).cloned() {
+            events.push(AlarmEvent {
+                name: alarm.name.clone(),
+                event: "fired",
+            });
+            self.snoozed = None;
+            self.ring(alarm, Duration::from_secs(self.config.sunrise * 60));
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(alarms: &mut Alarms, name: &str) -> Result<Option<AlarmEvent>, String> {
+        let value = format!(
+            r#"{{"action": "set", "name": "{}", "time": "07:00"}}"#,
+            name
+        );
+        alarms.command(AlarmCommand::parse(&value)?)
+    }
+
+    #[test]
+    fn saves_and_loads_the_alarms() {
+        let path = std::env::temp_dir().join(format!("alarms-{}.json", std::process::id()));
+        let mut alarms = Alarms::load(Some(path.clone()), AlarmConfig::default());
+        assert!(set(&mut alarms, "wake").is_ok());
+        let loaded = Alarms::load(Some(path.clone()), AlarmConfig::default());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.list(), alarms.list());
+        assert!(loaded.list().contains("wake"));
+    }
+
+    #[test]
+    fn keeps_the_alarms_when_saving_fails() {
+        let path = PathBuf::from("/nonexistent/alarms.json");
+        let mut alarms = Alarms::load(Some(path), AlarmConfig::default());
+        assert!(set(&mut alarms, "wake").is_err());
+        assert_eq!(alarms.list(), "[]");
+    }
+}
@@ -0,0 +1,144 @@
+use crate::state::RenderState;
+use std::io::Read;
+use std::sync::{Arc, RwLock};
+use tiny_http::{Method, Response, Server};
+
+// large enough for a custom app with a few animated icons
+const MAX_BODY: u64 = 256 * 1024;
+
+type ApiResult = Result<String, (u16, String)>;
+
+pub fn serve(port: u16, state: Arc<RwLock<RenderState>>) {
+    let server = match Server::http(("0.0.0.0", port)) {
+        Ok(server) => server,
+        Err(err) => {
+            println!("Cannot start api on port {}: {}", port, err);
+            return;
+        }
+    };
+    println!("Api listening on port {}", port);
+
+    for mut request in server.incoming_requests() {
+        let result = match read_body(request.as_reader()) {
+            Ok(body) => handle(&state, request.method(), request.url(), &body),
+            Err(err) => Err(err),
+        };
+        let response = match result {
+            Ok(body) => Response::from_string(body),
+            Err((code, msg)) => Response::from_string(msg).with_status_code(code),
+        };
+        if let Err(err) = request.respond(response) {
+            println!("Api response failed: {}", err);
+        }
+    }
+}
+
+fn read_body(reader: &mut dyn Read) -> Result<String, (u16, String)> {
+    let mut body = Vec::new();
+    reader
+        .take(MAX_BODY + 1)
+        .read_to_end(&mut body)
+        .map_err(|err| (400, err.to_string()))?;
+    if body.len() as u64 > MAX_BODY {
+        return Err((413, format!("body is larger than {} bytes", MAX_BODY)));
+    }
+    String::from_utf8(body).map_err(|err| (400, err.to_string()))
+}
+
+fn handle(state: &Arc<RwLock<RenderState>>, method: &Method, url: &str, body: &str) -> ApiResult {
+    let mut state = state
+        .write()
+        .map_err(|_| (500, "state is poisoned".to_string()))?;
+    let (path, query) = match url.find('?') {
+        Some(idx) => (&url[..idx], &url[idx + 1..]),
+        None => (url, ""),
+    };
+    match (method, path) {
+        (Method::Post, "/api/notify") => ok((*state).notify(body)),
+        (Method::Post, "/api/notify/dismiss") => {
+            (*state).dismiss_notification();
+            ok(Ok(()))
+        }
+        (Method::Post, "/api/timer") => ok((*state).timer(body)),
+        (Method::Post, "/api/stopwatch") => ok((*state).stopwatch(body)),
+        (Method::Post, "/api/alarm") => ok((*state).alarm(body)),
+        (Method::Post, "/api/pomodoro") => ok((*state).pomodoro(body)),
+        (Method::Post, "/api/settings") => ok((*state).set_settings(body)),
+        (Method::Post, "/api/power") => ok((*state).set_power(body)),
+        (Method::Post, "/api/switch") => ok((*state).switch_app(body)),
+        (Method::Post, "/api/indicator1") => ok((*state).set_indicator(1, body)),
+        (Method::Post, "/api/indicator2") => ok((*state).set_indicator(2, body)),
+        (Method::Post, "/api/indicator3") => ok((*state).set_indicator(3, body)),
+        (Method::Get, "/api/stats") => Ok((*state).stats()),
+        (Method::Get, "/api/alarms") => Ok((*state).alarms.list()),
+        (Method::Post, "/api/custom") => match param(query, "name") {
+            Some(name) => ok((*state).set_custom_app(&decode(name)?, body)),
+            None => Err((400, "missing name".to_string())),
+        },
+        _ => Err((404, format!("{} {} not found", method, url))),
+    }
+}
+
+fn param<'a>(query: &'a str, key: &str) -> Option<&'a str> {
+    query.split('&').find_map(|pair| {
+        let mut parts = pair.splitn(2, '=');
+        match (parts.next(), parts.next()) {
+            (Some(name), Some(value)) if name == key => Some(value),
+            _ => None,
+        }
+    })
+}
+
+// query values are percent-encoded, `+` stands for a space
+fn decode(value: &str) -> Result<String, (u16, String)> {
+    let invalid = || (400, format!("invalid query value {}", value));
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut iter = value.bytes();
+    while let Some(byte) = iter.next() {
+        bytes.push(match byte {
+            b'+' => b' ',
+            b'%' => {
+                let hex = [
+                    iter.next().ok_or_else(invalid)?,
+                    iter.next().ok_or_else(invalid)?,
+                ];
+                let hex = std::str::from_utf8(&hex).map_err(|_| invalid())?;
+                u8::from_str_radix(hex, 16).map_err(|_| invalid())?
+            }
+            byte => byte,
+        });
+    }
+    String::from_utf8(bytes).map_err(|_| invalid())
+}
+
+fn ok(result: Result<(), String>) -> ApiResult {
+    result.map(|_| "OK".to_string()).map_err(|err| (400, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_query_values() {
+        assert_eq!(decode("my%20app"), Ok("my app".to_string()));
+        assert_eq!(decode("my+app"), Ok("my app".to_string()));
+        assert_eq!(decode("caf%C3%A9"), Ok("caf\u{e9}".to_string()));
+        assert!(decode("my%2").is_err());
+        assert!(decode("my%zzapp").is_err());
+    }
+
+    #[test]
+    fn limits_the_body_size() {
+        let body = vec![b'a'; MAX_BODY as usize];
+        assert_eq!(
+            read_body(&mut &body[..]).map(|body| body.len()),
+            Ok(body.len())
+        );
+        let body = vec![b'a'; MAX_BODY as usize + 1];
+        assert_eq!(
+            read_body(&mut &body[..]).map_err(|(code, _)| code),
+            Err(413)
+        );
+    }
+}
@@ -0,0 +1,40 @@
+use crate::app::App;
+use crate::renderer::Frame;
+use crate::state::RenderState;
+use std::time::Duration;
+
+pub struct AnimationApp {
+    duration: Duration,
+}
+
+impl AnimationApp {
+    pub fn new() -> AnimationApp {
+        let secs = std::env::var("GIF_DURATION")
+            .ok()
+            .and_then(|val| val.parse::<u64>().ok())
+            .unwrap_or(5);
+        AnimationApp {
+            duration: Duration::from_secs(secs),
+        }
+    }
+}
+
+impl App for AnimationApp {
+    fn name(&self) -> &str {
+        "animation"
+    }
+
+    fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    fn is_available(&self, state: &RenderState) -> bool {
+        state.animation.is_some()
+    }
+
+    fn render(&self, state: &RenderState, frame: &mut Frame, elapsed: Duration) {
+        if let Some(animation) = &state.animation {
+            frame.draw_sprite(animation, elapsed, 0, 0);
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use crate::app::date::DateConfig;
+use crate::app::{draw_weekday_bar, App};
+use crate::bitmap::Bitmap;
+use crate::locale::Locale;
+use crate::renderer::{Frame, COLS};
+use crate::state::RenderState;
+use chrono::{Datelike, NaiveDate};
+use serde::Deserialize;
+use std::time::Duration;
+
+// the calendar sheet on the left, the month and weekday bar after it
+const SHEET_WIDTH: usize = 9;
+const INFO_X: usize = SHEET_WIDTH + 1;
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CalendarConfig {
+    // a bar along the top row showing how far the month is through
+    pub month_progress: bool,
+}
+
+pub struct CalendarApp {
+    locale: Locale,
+    sunday_first: bool,
+    month_progress: bool,
+}
+
+impl CalendarApp {
+    // the locale and first weekday are shared with the date app
+    pub fn new(date: &DateConfig, config: CalendarConfig) -> CalendarApp {
+        CalendarApp {
+            locale: date.locale,
+            sunday_first: date.sunday_first,
+            month_progress: config.month_progress,
+        }
+    }
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    let next = if month == 12 {
+        NaiveDate::from_ymd(year + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd(year, month + 1, 1)
+    };
+    next.pred().day()
+}
+
+impl App for CalendarApp {
+    fn name(&self) -> &str {
+        "calendar"
+    }
+
+    fn duration(&self) -> Duration {
+        Duration::from_secs(2)
+    }
+
+    fn render(&self, state: &RenderState, frame: &mut Frame, _elapsed: Duration) {
+        let now = state.now();
+
+        frame.fill_rect(&state.theme.calendar_sheet, 0, 0, SHEET_WIDTH, 2);
+        let day = now.day().to_string();
+        let day_x = (SHEET_WIDTH - Bitmap::text_width(&day)) / 2;
+        frame.draw_text(&day, &state.theme.calendar_day, day_x as isize, 2);
+
+        let month = self.locale.format("%b", &now);
+        let width = COLS - INFO_X;
+        let month_x = INFO_X + width.saturating_sub(Bitmap::text_width(&month)) / 2;
+        frame.draw_text_with(
+            &month,
+            |char| state.theme.color_for(char, &state.color),
+            month_x as isize,
+            1,
+        );
+        draw_weekday_bar(state, frame, INFO_X, width, self.sunday_first);
+
+        if self.month_progress {
+            let days = days_in_month(now.year(), now.month());
+            let filled = width * now.day() as usize / days as usize;
+            frame.fill_rect(&state.color, INFO_X, 0, filled, 1);
+        }
+    }
+}
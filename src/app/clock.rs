@@ -0,0 +1,123 @@
+use crate::app::{is_valid_format, App};
+use crate::bitmap::Bitmap;
+use crate::renderer::{Frame, COLS, ROWS};
+use crate::state::RenderState;
+use chrono::{DateTime, FixedOffset, Timelike};
+use serde::Deserialize;
+use std::time::Duration;
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColonStyle {
+    Blink,
+    Steady,
+    Fade,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct ClockConfig {
+    // chrono format, replaces the one built from the options below
+    pub format: Option<String>,
+    pub hour12: bool,
+    pub seconds: bool,
+    pub colon: ColonStyle,
+}
+
+impl Default for ClockConfig {
+    fn default() -> ClockConfig {
+        ClockConfig {
+            format: None,
+            hour12: false,
+            seconds: true,
+            colon: ColonStyle::Blink,
+        }
+    }
+}
+
+pub struct ClockApp {
+    config: ClockConfig,
+}
+
+impl ClockApp {
+    pub fn new(mut config: ClockConfig) -> ClockApp {
+        if let Some(format) = &config.format {
+            if !is_valid_format(format) {
+                println!("Invalid clock format {}, using the default", format);
+                config.format = None;
+            }
+        }
+        ClockApp { config }
+    }
+
+    fn format(&self) -> &str {
+        if let Some(format) = &self.config.format {
+            return format;
+        }
+        match (self.config.hour12, self.config.seconds) {
+            (false, true) => "%H:%M:%S",
+            (false, false) => "%H:%M",
+            (true, true) => "%I:%M:%S",
+            (true, false) => "%I:%M",
+        }
+    }
+
+    // HH:MM fits in the 8 rows tall digits
+    fn is_big(&self) -> bool {
+        self.config.format.is_none() && !self.config.seconds
+    }
+
+    fn format_time(&self, now: &DateTime<FixedOffset>) -> String {
+        let text = now.format(self.format()).to_string();
+        match self.config.colon {
+            ColonStyle::Blink if now.timestamp_subsec_millis() >= 500 => text.replace(':', " "),
+            _ => text,
+        }
+    }
+}
+
+impl App for ClockApp {
+    fn name(&self) -> &str {
+        "clock"
+    }
+
+    fn duration(&self) -> Duration {
+        Duration::from_secs(15)
+    }
+
+    fn render(&self, state: &RenderState, frame: &mut Frame, _elapsed: Duration) {
+        let now = state.now();
+        let text = self.format_time(&now);
+        let color_of = |char| state.theme.color_for(char, &state.color);
+        let big = self.is_big();
+        let (text_width, y, height): (fn(&str) -> usize, usize, usize) = if big {
+            (Bitmap::big_text_width, 0, ROWS)
+        } else {
+            (Bitmap::text_width, 1, 5)
+        };
+        let x = COLS.saturating_sub(text_width(&text)) / 2;
+        if big {
+            frame.draw_big_text_with(&text, color_of, x as isize, y);
+        } else {
+            frame.draw_text_with(&text, color_of, x as isize, y);
+        }
+
+        if let ColonStyle::Fade = self.config.colon {
+            let phase = now.timestamp_subsec_millis() as f32 / 1000f32;
+            let level = (phase * 2f32 * std::f32::consts::PI).cos() * 0.5 + 0.5;
+            for (idx, _) in text.match_indices(':') {
+                let colon_x = match idx {
+                    0 => x,
+                    _ => x + text_width(&text[..idx]) + 1,
+                };
+                frame.dim_rect(level, colon_x, y, 1, height);
+            }
+        }
+
+        // a dot on the left edge, top for AM and bottom for PM
+        if self.config.hour12 {
+            let y = if now.hour() < 12 { 1 } else { 5 };
+            frame.draw_pixel(state.theme.color_for('M', &state.color), 0, y);
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use crate::app::{draw_progress, draw_scroll_text, App};
+use crate::renderer::{Color, Frame};
+use crate::state::RenderState;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+
+fn default_duration() -> u64 {
+    5
+}
+
+#[derive(Deserialize)]
+pub struct CustomAppData {
+    pub text: String,
+    pub icon: Option<String>,
+    pub color: Option<Color>,
+    #[serde(default)]
+    pub rainbow: bool,
+    // seconds on screen, takes precedence over ATIME
+    pub duration: Option<u64>,
+    // 0-100, drawn as a bar on the bottom row
+    pub progress: Option<u8>,
+    #[serde(alias = "progressC")]
+    pub progress_color: Option<Color>,
+    // seconds, the app is removed when it is not updated in time
+    pub lifetime: Option<u64>,
+}
+
+pub struct CustomApp {
+    name: String,
+    data: CustomAppData,
+    updated: Instant,
+}
+
+impl CustomApp {
+    pub fn parse(name: &str, value: &str) -> Result<CustomApp, String> {
+        let data: CustomAppData = serde_json::from_str(value).map_err(|err| err.to_string())?;
+        if let Some(progress) = data.progress {
+            if progress > 100 {
+                return Err(format!("progress {} is not in 0-100", progress));
+            }
+        }
+
+        Ok(CustomApp {
+            name: name.to_string(),
+            data,
+            updated: Instant::now(),
+        })
+    }
+}
+
+impl App for CustomApp {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn duration(&self) -> Duration {
+        Duration::from_secs(self.data.duration.unwrap_or_else(default_duration))
+    }
+
+    fn own_duration(&self) -> Option<Duration> {
+        self.data.duration.map(Duration::from_secs)
+    }
+
+    fn is_available(&self, _state: &RenderState) -> bool {
+        !self.is_expired()
+    }
+
+    fn is_custom(&self) -> bool {
+        true
+    }
+
+    fn is_expired(&self) -> bool {
+        match self.data.lifetime {
+            Some(lifetime) => self.updated.elapsed() > Duration::from_secs(lifetime),
+            None => false,
+        }
+    }
+
+    fn render(&self, state: &RenderState, frame: &mut Frame, elapsed: Duration) {
+        let color = match (self.data.rainbow, &self.data.color) {
+            (true, _) => &Color::Rainbow,
+            (false, Some(color)) => color,
+            (false, None) => &state.color,
+        };
+        draw_scroll_text(
+            state,
+            frame,
+            elapsed,
+            self.data.icon.as_deref(),
+            &self.data.text.to_uppercase(),
+            color,
+        );
+
+        if let Some(progress) = self.data.progress {
+            draw_progress(
+                state,
+                frame,
+                self.data.icon.as_deref(),
+                progress,
+                self.data.progress_color.as_ref().unwrap_or(color),
+            );
+        }
+    }
+}
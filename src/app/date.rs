@@ -0,0 +1,93 @@
+use crate::app::{draw_text, draw_weekday_bar, is_valid_format, text_start, App};
+use crate::bitmap::Bitmap;
+use crate::locale::Locale;
+use crate::renderer::{Frame, COLS};
+use crate::state::RenderState;
+use serde::Deserialize;
+use std::time::Duration;
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DateOrder {
+    // month first, e.g. 10-19
+    Md,
+    // day first, e.g. 19-10
+    Dm,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct DateConfig {
+    // chrono format, replaces the one built from the options below
+    pub format: Option<String>,
+    pub locale: Locale,
+    pub order: DateOrder,
+    // shows the weekday as a bar along the bottom row instead of text
+    pub weekday_bar: bool,
+    pub sunday_first: bool,
+}
+
+impl Default for DateConfig {
+    fn default() -> DateConfig {
+        DateConfig {
+            format: None,
+            locale: Locale::En,
+            order: DateOrder::Md,
+            weekday_bar: false,
+            sunday_first: false,
+        }
+    }
+}
+
+pub struct DateApp {
+    config: DateConfig,
+}
+
+impl DateApp {
+    pub fn new(mut config: DateConfig) -> DateApp {
+        if let Some(format) = &config.format {
+            if !is_valid_format(format) {
+                println!("Invalid date format {}, using the default", format);
+                config.format = None;
+            }
+        }
+        DateApp { config }
+    }
+
+    fn format(&self) -> &str {
+        if let Some(format) = &self.config.format {
+            return format;
+        }
+        match (self.config.order, self.config.weekday_bar) {
+            (DateOrder::Md, false) => "%m-%d_%a",
+            (DateOrder::Dm, false) => "%d-%m_%a",
+            (DateOrder::Md, true) => "%m-%d",
+            (DateOrder::Dm, true) => "%d-%m",
+        }
+    }
+}
+
+impl App for DateApp {
+    fn name(&self) -> &str {
+        "date"
+    }
+
+    fn duration(&self) -> Duration {
+        Duration::from_secs(2)
+    }
+
+    fn render(&self, state: &RenderState, frame: &mut Frame, elapsed: Duration) {
+        let text = self.config.locale.format(self.format(), &state.now());
+        let x = if self.config.weekday_bar {
+            COLS.saturating_sub(Bitmap::text_width(&text)) / 2
+        } else {
+            1
+        };
+        draw_text(state, frame, elapsed, "date", &text, &state.color, x);
+
+        if self.config.weekday_bar {
+            let start = text_start(state, Some("date"));
+            draw_weekday_bar(state, frame, start, COLS - start, self.config.sunday_first);
+        }
+    }
+}
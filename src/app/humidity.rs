@@ -0,0 +1,42 @@
+use crate::app::{draw_text, App};
+use crate::renderer::Frame;
+use crate::state::RenderState;
+use std::time::Duration;
+
+pub struct HumidityApp;
+
+impl App for HumidityApp {
+    fn name(&self) -> &str {
+        "humidity"
+    }
+
+    fn duration(&self) -> Duration {
+        Duration::from_secs(2)
+    }
+
+    fn is_available(&self, state: &RenderState) -> bool {
+        state.humidity.is_some() && !state.is_temperature_humidity_stale()
+    }
+
+    fn render(&self, state: &RenderState, frame: &mut Frame, elapsed: Duration) {
+        let value = match state.humidity {
+            Some(value) => value,
+            None => return,
+        };
+        let color = state
+            .theme
+            .humidity_scale
+            .as_ref()
+            .and_then(|scale| scale.color_at(value))
+            .unwrap_or_else(|| state.color.clone());
+        draw_text(
+            state,
+            frame,
+            elapsed,
+            "humidity",
+            &format!("{:.2}%", value),
+            &color,
+            6,
+        );
+    }
+}
@@ -0,0 +1,528 @@
+use crate::bitmap::Bitmap;
+use crate::icon::ICON_SIZE;
+use crate::renderer::{Color, Frame, COLS, ROWS};
+use crate::state::RenderState;
+use chrono::format::{Item, StrftimeItems};
+use chrono::Datelike;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+pub mod animation;
+pub mod calendar;
+pub mod clock;
+pub mod custom;
+pub mod date;
+pub mod humidity;
+pub mod pomodoro;
+pub mod pressure;
+pub mod temperature;
+pub mod timer;
+pub mod world_clock;
+
+pub const SCROLL_STEP: Duration = Duration::from_millis(60);
+// shown instead of a blank screen when no playlist entry is available
+const FALLBACK_APP: &str = "clock";
+
+pub trait App: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn duration(&self) -> Duration;
+
+    // a duration given with the app itself, it wins over the global default
+    fn own_duration(&self) -> Option<Duration> {
+        None
+    }
+
+    fn is_available(&self, _state: &RenderState) -> bool {
+        true
+    }
+
+    // custom apps are created and removed at runtime, built-in apps stay registered
+    fn is_custom(&self) -> bool {
+        false
+    }
+
+    fn is_expired(&self) -> bool {
+        false
+    }
+
+    // `elapsed` is the time since the app came on screen
+    fn render(&self, state: &RenderState, frame: &mut Frame, elapsed: Duration);
+}
+
+// draws the icon when it is loaded and the text after it, otherwise the text starts at `x`
+pub fn draw_text(
+    state: &RenderState,
+    frame: &mut Frame,
+    elapsed: Duration,
+    icon: &str,
+    text: &str,
+    color: &Color,
+    x: usize,
+) {
+    let x = match state.icons.get(icon) {
+        Some(icon) => {
+            frame.draw_sprite(icon, elapsed, 0, 0);
+            ICON_SIZE + 1
+        }
+        None => x,
+    };
+    frame.draw_text_with(
+        text,
+        |char| state.theme.color_for(char, color),
+        x as isize,
+        1,
+    );
+}
+
+fn text_start(state: &RenderState, icon: Option<&str>) -> usize {
+    match icon.and_then(|name| state.icons.get(name)) {
+        Some(_) => ICON_SIZE + 1,
+        None => 0,
+    }
+}
+
+// the time to scroll the text through once, None when it fits on screen
+pub fn scroll_time(state: &RenderState, icon: Option<&str>, text: &str) -> Option<Duration> {
+    let start = text_start(state, icon);
+    let width = Bitmap::text_width(text);
+    if start + width <= COLS {
+        None
+    } else {
+        Some(SCROLL_STEP * (width + COLS - start) as u32)
+    }
+}
+
+// centres the text after the icon, or scrolls it from right to left when it does not fit
+pub fn draw_scroll_text(
+    state: &RenderState,
+    frame: &mut Frame,
+    elapsed: Duration,
+    icon: Option<&str>,
+    text: &str,
+    color: &Color,
+) {
+    let start = text_start(state, icon);
+    let width = Bitmap::text_width(text);
+    let x = if start + width <= COLS {
+        (start + (COLS - start - width) / 2) as isize
+    } else {
+        let distance = width + COLS - start;
+        let step = (elapsed.as_millis() / SCROLL_STEP.as_millis()) as usize % distance;
+        COLS as isize - step as isize
+    };
+    frame.draw_text(text, color, x, 1);
+
+    if let Some(icon) = icon.and_then(|name| state.icons.get(name)) {
+        frame.fill_rect(&Color::Black, 0, 0, start, ROWS);
+        frame.draw_sprite(icon, elapsed, 0, 0);
+    }
+}
+
+// alternates between the text on a filled screen and the text in the colour
+pub fn draw_flash(
+    state: &RenderState,
+    frame: &mut Frame,
+    elapsed: Duration,
+    text: &str,
+    color: &Color,
+) {
+    let on = (elapsed.as_millis() / 250) % 2 == 0;
+    if on {
+        frame.fill_rect(color, 0, 0, COLS, ROWS);
+    }
+    let text_color = if on { &Color::Black } else { color };
+    draw_scroll_text(state, frame, elapsed, None, text, text_color);
+}
+
+// a bar on the bottom row after the icon, `progress` is 0-100
+pub fn draw_progress(
+    state: &RenderState,
+    frame: &mut Frame,
+    icon: Option<&str>,
+    progress: u8,
+    color: &Color,
+) {
+    let start = text_start(state, icon);
+    let filled = (COLS - start) * progress.min(100) as usize / 100;
+    frame.fill_rect(color, start, ROWS - 1, filled, 1);
+}
+
+// 7 segments along the bottom row of the area with today highlighted and the other days dimmed
+pub fn draw_weekday_bar(
+    state: &RenderState,
+    frame: &mut Frame,
+    x: usize,
+    width: usize,
+    sunday_first: bool,
+) {
+    let weekday = if sunday_first {
+        state.now().weekday().num_days_from_sunday()
+    } else {
+        state.now().weekday().num_days_from_monday()
+    } as usize;
+    let segment = (width + 1) / 7 - 1;
+    let x = x + (width - (7 * (segment + 1) - 1)) / 2;
+    let color = state.theme.color_for('0', &state.color);
+    for day in 0..7 {
+        let day_x = x + day * (segment + 1);
+        frame.fill_rect(color, day_x, ROWS - 1, segment, 1);
+        if day != weekday {
+            frame.dim_rect(0.25, day_x, ROWS - 1, segment, 1);
+        }
+    }
+}
+
+// chrono panics when an invalid format is displayed, so check it up front
+pub fn is_valid_format(format: &str) -> bool {
+    !StrftimeItems::new(format).any(|item| item == Item::Error)
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Clone, Deserialize)]
+pub struct PlaylistEntry {
+    pub app: String,
+    // seconds, otherwise a custom app's own duration, ATIME or the app's default
+    pub duration: Option<u64>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_true")]
+    pub skip_if_stale: bool,
+}
+
+impl PlaylistEntry {
+    pub fn new(app: &str) -> PlaylistEntry {
+        PlaylistEntry {
+            app: app.to_string(),
+            duration: None,
+            enabled: true,
+            skip_if_stale: true,
+        }
+    }
+}
+
+pub struct Scheduler {
+    apps: HashMap<String, Box<dyn App>>,
+    playlist: Vec<PlaylistEntry>,
+    current: usize,
+    since: Instant,
+    paused_at: Option<Instant>,
+    // overrides the apps' own duration, a playlist entry's duration still wins
+    default_duration: Option<Duration>,
+    // set while nothing is available, until `next` finds an entry again
+    fallback: bool,
+}
+
+impl Scheduler {
+    pub fn new() -> Scheduler {
+        Scheduler {
+            apps: HashMap::new(),
+            playlist: Vec::new(),
+            current: 0,
+            since: Instant::now(),
+            paused_at: None,
+            default_duration: None,
+            fallback: false,
+        }
+    }
+
+    // new apps are added to the end of the playlist unless it already refers to them,
+    // a known app is replaced in place
+    pub fn register(&mut self, app: Box<dyn App>) {
+        if !self.apps.contains_key(app.name())
+            && !self.playlist.iter().any(|entry| entry.app == app.name())
+        {
+            self.playlist.push(PlaylistEntry::new(app.name()));
+        }
+        self.apps.insert(app.name().to_string(), app);
+    }
+
+    // removes the app and its playlist entries, the current app keeps running if it is not the one
+    pub fn unregister(&mut self, name: &str) {
+        if self.apps.remove(name).is_none() {
+            return;
+        }
+
+        let current = self
+            .playlist
+            .get(self.current)
+            .map(|entry| entry.app.clone());
+        self.playlist.retain(|entry| entry.app != name);
+        match self
+            .playlist
+            .iter()
+            .position(|entry| Some(&entry.app) == current.as_ref())
+        {
+            Some(idx) => self.current = idx,
+            None => {
+                self.current = 0;
+                self.since = Instant::now();
+            }
+        }
+    }
+
+    pub fn remove_expired(&mut self) {
+        let expired: Vec<String> = self
+            .apps
+            .values()
+            .filter(|app| app.is_expired())
+            .map(|app| app.name().to_string())
+            .collect();
+        for name in expired {
+            println!("Custom app {} expired", name);
+            self.unregister(&name);
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn App> {
+        self.apps.get(name).map(|app| app.as_ref())
+    }
+
+    // entries of apps that are not registered are kept for custom apps published later,
+    // until then they are skipped like an unavailable app
+    pub fn set_playlist(&mut self, playlist: Vec<PlaylistEntry>) -> Result<(), String> {
+        for entry in playlist.iter() {
+            if !self.apps.contains_key(&entry.app) {
+                println!("Playlist app {} is not registered yet", entry.app);
+            }
+        }
+        if !playlist.iter().any(|entry| entry.enabled) {
+            return Err("playlist has no enabled app".to_string());
+        }
+
+        self.playlist = playlist;
+        self.current = 0;
+        self.since = Instant::now();
+        self.fallback = false;
+        Ok(())
+    }
+
+    pub fn set_default_duration(&mut self, duration: Option<Duration>) {
+        self.default_duration = duration;
+    }
+
+    pub fn set_enabled(&mut self, app: &str, enabled: bool) {
+        self.playlist
+            .iter_mut()
+            .filter(|entry| entry.app == app)
+            .for_each(|entry| entry.enabled = enabled);
+    }
+
+    pub fn playlist(&self) -> &[PlaylistEntry] {
+        &self.playlist
+    }
+
+    pub fn current(&self) -> Option<&dyn App> {
+        if self.fallback {
+            return self.apps.get(FALLBACK_APP).map(|app| app.as_ref());
+        }
+        self.playlist
+            .get(self.current)
+            .and_then(|entry| self.apps.get(&entry.app))
+            .map(|app| app.as_ref())
+    }
+
+    // shows the first playlist entry of the app now
+    pub fn switch_to(&mut self, app: &str) -> Result<(), String> {
+        if !self.apps.contains_key(app) {
+            return Err(format!("unknown app '{}'", app));
+        }
+        match self.playlist.iter().position(|entry| entry.app == app) {
+            Some(idx) => {
+                self.current = idx;
+                self.since = Instant::now();
+                self.fallback = false;
+                Ok(())
+            }
+            None => Err(format!("'{}' is not in the playlist", app)),
+        }
+    }
+
+    // holds the current app while something else is on screen
+    pub fn pause(&mut self) {
+        if self.paused_at.is_none() {
+            self.paused_at = Some(Instant::now());
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if let Some(paused_at) = self.paused_at.take() {
+            self.since += paused_at.elapsed();
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.since.elapsed()
+    }
+
+    fn duration(&self, idx: usize) -> Duration {
+        let entry = &self.playlist[idx];
+        match (entry.duration, self.apps.get(&entry.app)) {
+            (Some(secs), _) => Duration::from_secs(secs),
+            (None, Some(app)) => app
+                .own_duration()
+                .or(self.default_duration)
+                .unwrap_or_else(|| app.duration()),
+            (None, None) => Duration::from_secs(0),
+        }
+    }
+
+    pub fn availability(&self, state: &RenderState) -> Vec<bool> {
+        self.playlist
+            .iter()
+            .map(|entry| match self.apps.get(&entry.app) {
+                Some(app) => entry.enabled && (!entry.skip_if_stale || app.is_available(state)),
+                None => false,
+            })
+            .collect()
+    }
+
+    // `available` is the result of `availability`, one flag per playlist entry
+    pub fn next(&mut self, available: &[bool]) {
+        if !self.fallback
+            && !self.playlist.is_empty()
+            && available[self.current]
+            && self.since.elapsed() < self.duration(self.current)
+        {
+            return;
+        }
+
+        if !self.skip(available, false) && !self.fallback {
+            self.fallback = true;
+            self.since = Instant::now();
+        }
+    }
+
+    // shows the next available entry now, or the one before when `backwards`
+    pub fn skip(&mut self, available: &[bool], backwards: bool) -> bool {
+        let len = self.playlist.len();
+        for offset in 1..=len {
+            let idx = if backwards {
+                (self.current + len - offset) % len
+            } else {
+                (self.current + offset) % len
+            };
+            if available[idx] {
+                self.current = idx;
+                self.since = Instant::now();
+                self.fallback = false;
+                return true;
+            }
+        }
+        false
+    }
+
+    pub fn render(&self, state: &RenderState, frame: &mut Frame) {
+        if let Some(app) = self.current() {
+            app.render(state, frame, self.elapsed());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestApp {
+        name: &'static str,
+        duration: u64,
+        own_duration: Option<u64>,
+    }
+
+    impl App for TestApp {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn duration(&self) -> Duration {
+            Duration::from_secs(self.duration)
+        }
+
+        fn own_duration(&self) -> Option<Duration> {
+            self.own_duration.map(Duration::from_secs)
+        }
+
+        fn render(&self, _state: &RenderState, _frame: &mut Frame, _elapsed: Duration) {}
+    }
+
+    fn scheduler(names: &[&'static str]) -> Scheduler {
+        let mut scheduler = Scheduler::new();
+        for name in names {
+            scheduler.register(Box::new(TestApp {
+                name,
+                duration: 5,
+                own_duration: None,
+            }));
+        }
+        scheduler
+    }
+
+    fn current(scheduler: &Scheduler) -> Option<&str> {
+        scheduler.current().map(|app| app.name())
+    }
+
+    #[test]
+    fn skips_unavailable_apps() {
+        let mut scheduler = scheduler(&["clock", "a", "b"]);
+        assert_eq!(current(&scheduler), Some("clock"));
+        scheduler.next(&[false, false, true]);
+        assert_eq!(current(&scheduler), Some("b"));
+    }
+
+    #[test]
+    fn falls_back_to_the_clock_when_nothing_is_available() {
+        let mut scheduler = scheduler(&["clock", "a", "b"]);
+        scheduler
+            .set_playlist(vec![PlaylistEntry::new("a"), PlaylistEntry::new("b")])
+            .unwrap();
+        scheduler.next(&[false, false]);
+        assert_eq!(current(&scheduler), Some("clock"));
+        scheduler.next(&[false, false]);
+        assert_eq!(current(&scheduler), Some("clock"));
+        scheduler.next(&[false, true]);
+        assert_eq!(current(&scheduler), Some("b"));
+    }
+
+    #[test]
+    fn takes_the_entry_then_the_app_then_the_default_duration() {
+        let mut scheduler = scheduler(&["clock"]);
+        scheduler.register(Box::new(TestApp {
+            name: "custom",
+            duration: 5,
+            own_duration: Some(7),
+        }));
+        let mut entry = PlaylistEntry::new("clock");
+        entry.duration = Some(20);
+        scheduler
+            .set_playlist(vec![
+                entry,
+                PlaylistEntry::new("clock"),
+                PlaylistEntry::new("custom"),
+            ])
+            .unwrap();
+        let secs = |scheduler: &Scheduler| -> Vec<u64> {
+            (0..3)
+                .map(|idx| scheduler.duration(idx).as_secs())
+                .collect()
+        };
+        assert_eq!(secs(&scheduler), vec![20, 5, 7]);
+
+        scheduler.set_default_duration(Some(Duration::from_secs(10)));
+        assert_eq!(secs(&scheduler), vec![20, 10, 7]);
+    }
+
+    #[test]
+    fn skips_in_both_directions() {
+        let mut scheduler = scheduler(&["clock", "a", "b"]);
+        assert!(scheduler.skip(&[true, true, true], true));
+        assert_eq!(current(&scheduler), Some("b"));
+        assert!(scheduler.skip(&[true, false, true], false));
+        assert_eq!(current(&scheduler), Some("clock"));
+        assert!(!scheduler.skip(&[false, false, false], false));
+        assert_eq!(current(&scheduler), Some("clock"));
+    }
+}
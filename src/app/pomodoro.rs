@@ -0,0 +1,55 @@
+use crate::app::{draw_progress, draw_scroll_text, text_start, App};
+use crate::pomodoro::Phase;
+use crate::renderer::Frame;
+use crate::state::RenderState;
+use std::time::Duration;
+
+// shows the time left in the phase while a pomodoro is running
+pub struct PomodoroApp;
+
+impl App for PomodoroApp {
+    fn name(&self) -> &str {
+        "pomodoro"
+    }
+
+    fn duration(&self) -> Duration {
+        Duration::from_secs(5)
+    }
+
+    fn is_available(&self, state: &RenderState) -> bool {
+        state.pomodoro.session.is_some()
+    }
+
+    fn render(&self, state: &RenderState, frame: &mut Frame, elapsed: Duration) {
+        let session = match &state.pomodoro.session {
+            Some(session) => session,
+            None => return,
+        };
+        let icon = if state.icons.get("pomodoro").is_some() {
+            Some("pomodoro")
+        } else {
+            None
+        };
+        let color = session.phase.color();
+        draw_scroll_text(
+            state,
+            frame,
+            elapsed,
+            icon,
+            &session.timer.text(),
+            &state.color,
+        );
+        draw_progress(state, frame, icon, session.timer.progress(), &color);
+
+        // a dot on the top row for each work session of the current cycle
+        let cycles = state.pomodoro.cycles();
+        let done = match session.phase {
+            Phase::Work => session.completed % cycles,
+            Phase::ShortBreak | Phase::LongBreak => (session.completed + cycles - 1) % cycles + 1,
+        };
+        let start = text_start(state, icon);
+        for idx in 0..done as usize {
+            frame.draw_pixel(&color, start + idx * 2, 0);
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use crate::app::{draw_text, App};
+use crate::renderer::Frame;
+use crate::state::RenderState;
+use std::time::Duration;
+
+pub struct PressureApp;
+
+impl App for PressureApp {
+    fn name(&self) -> &str {
+        "pressure"
+    }
+
+    fn duration(&self) -> Duration {
+        Duration::from_secs(2)
+    }
+
+    fn is_available(&self, state: &RenderState) -> bool {
+        state.pressure.is_some() && !state.is_temperature_humidity_stale()
+    }
+
+    fn render(&self, state: &RenderState, frame: &mut Frame, elapsed: Duration) {
+        let value = match state.pressure {
+            Some(value) => value,
+            None => return,
+        };
+        // the unit does not fit next to the icon
+        let text = if state.icons.get("pressure").is_some() {
+            format!("{:.0}", value)
+        } else {
+            format!("{:.0}hPa", value)
+        };
+        draw_text(state, frame, elapsed, "pressure", &text, &state.color, 3);
+    }
+}
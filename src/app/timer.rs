@@ -0,0 +1,27 @@
+use crate::app::App;
+use crate::renderer::Frame;
+use crate::state::RenderState;
+use std::time::Duration;
+
+// shows the timer that ends first while timers are running
+pub struct TimerApp;
+
+impl App for TimerApp {
+    fn name(&self) -> &str {
+        "timer"
+    }
+
+    fn duration(&self) -> Duration {
+        Duration::from_secs(5)
+    }
+
+    fn is_available(&self, state: &RenderState) -> bool {
+        state.timers.next_due().is_some()
+    }
+
+    fn render(&self, state: &RenderState, frame: &mut Frame, _elapsed: Duration) {
+        if let Some(timer) = state.timers.next_due() {
+            timer.render(state, frame);
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use crate::app::{draw_scroll_text, is_valid_format, App};
+use crate::renderer::Frame;
+use crate::state::RenderState;
+use chrono::Utc;
+use chrono_tz::Tz;
+use serde::Deserialize;
+use std::time::Duration;
+
+#[derive(Clone, Deserialize)]
+pub struct City {
+    // a short label like "LDN"
+    pub label: String,
+    pub timezone: Tz,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct WorldClockConfig {
+    pub cities: Vec<City>,
+    // seconds per city
+    pub duration: u64,
+    // chrono format
+    pub format: String,
+}
+
+impl Default for WorldClockConfig {
+    fn default() -> WorldClockConfig {
+        WorldClockConfig {
+            cities: Vec::new(),
+            duration: 3,
+            format: "%H:%M".to_string(),
+        }
+    }
+}
+
+pub struct WorldClockApp {
+    config: WorldClockConfig,
+}
+
+impl WorldClockApp {
+    pub fn new(mut config: WorldClockConfig) -> WorldClockApp {
+        if !is_valid_format(&config.format) {
+            println!(
+                "Invalid world clock format {}, using the default",
+                config.format
+            );
+            config.format = WorldClockConfig::default().format;
+        }
+        config.duration = config.duration.max(1);
+        WorldClockApp { config }
+    }
+}
+
+impl App for WorldClockApp {
+    fn name(&self) -> &str {
+        "world_clock"
+    }
+
+    // every city is shown once
+    fn duration(&self) -> Duration {
+        Duration::from_secs(self.config.duration * self.config.cities.len() as u64)
+    }
+
+    fn is_available(&self, _state: &RenderState) -> bool {
+        !self.config.cities.is_empty()
+    }
+
+    fn render(&self, state: &RenderState, frame: &mut Frame, elapsed: Duration) {
+        if self.config.cities.is_empty() {
+            return;
+        }
+        let idx = (elapsed.as_secs() / self.config.duration) as usize % self.config.cities.len();
+        let city = &self.config.cities[idx];
+        let time = Utc::now().with_timezone(&city.timezone);
+        let text = format!(
+            "{} {}",
+            city.label.to_uppercase(),
+            time.format(&self.config.format)
+        );
+        draw_scroll_text(state, frame, elapsed, None, &text, &state.color);
+    }
+}
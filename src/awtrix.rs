@@ -0,0 +1,161 @@
+use crate::renderer::{Color, Frame};
+use serde::{de, Deserialize, Deserializer};
+use std::time::Instant;
+
+// the subset of the AWTRIX 3 settings that maps onto this clock
+#[derive(Deserialize)]
+pub struct Settings {
+    // 0-255, switches the auto brightness off
+    #[serde(rename = "BRI")]
+    pub brightness: Option<u8>,
+    #[serde(rename = "ABRI")]
+    pub auto_brightness: Option<bool>,
+    #[serde(rename = "TCOL")]
+    pub color: Option<Color>,
+    // seconds each app stays on screen
+    #[serde(rename = "ATIME")]
+    pub app_time: Option<u64>,
+    #[serde(rename = "TIM")]
+    pub clock: Option<bool>,
+    #[serde(rename = "DAT")]
+    pub date: Option<bool>,
+    #[serde(rename = "TEMP")]
+    pub temperature: Option<bool>,
+    #[serde(rename = "HUM")]
+    pub humidity: Option<bool>,
+}
+
+impl Settings {
+    pub fn parse(value: &str) -> Result<Settings, String> {
+        serde_json::from_str(value).map_err(|err| err.to_string())
+    }
+
+    // the built-in apps to switch on or off, DAT covers both date apps
+    pub fn apps(&self) -> Vec<(&'static str, bool)> {
+        let apps: [(&[&'static str], Option<bool>); 4] = [
+            (&["clock"], self.clock),
+            (&["calendar", "date"], self.date),
+            (&["temperature"], self.temperature),
+            (&["humidity"], self.humidity),
+        ];
+        apps.iter()
+            .filter_map(|(apps, enabled)| enabled.map(|enabled| (apps, enabled)))
+            .flat_map(|(apps, enabled)| apps.iter().map(move |app| (*app, enabled)))
+            .collect()
+    }
+}
+
+#[derive(Deserialize)]
+struct Power {
+    power: bool,
+}
+
+// accepts {"power": true} like AWTRIX, or a plain 1 / 0
+pub fn parse_power(value: &str) -> Result<bool, String> {
+    match value.trim() {
+        "1" | "true" | "on" => Ok(true),
+        "0" | "false" | "off" => Ok(false),
+        value => serde_json::from_str::<Power>(value)
+            .map(|power| power.power)
+            .map_err(|err| err.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct Switch {
+    name: String,
+}
+
+// accepts {"name": "clock"} like AWTRIX, or the plain app name
+pub fn parse_switch(value: &str) -> Result<String, String> {
+    let name = if value.trim_start().starts_with('{') {
+        serde_json::from_str::<Switch>(value)
+            .map(|switch| switch.name)
+            .map_err(|err| err.to_string())?
+    } else {
+        value.trim().to_string()
+    };
+    if name.is_empty() {
+        return Err("missing app name".to_string());
+    }
+    Ok(name)
+}
+
+// pixels of indicator 1-3 on the right edge, top to bottom
+const INDICATOR_PIXELS: [&[(usize, usize)]; 3] = [
+    &[(31, 0), (30, 0), (31, 1)],
+    &[(31, 3), (31, 4)],
+    &[(31, 7), (30, 7), (31, 6)],
+];
+
+fn indicator_color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+    // AWTRIX turns an indicator off with "0"
+    let value = serde_json::Value::deserialize(deserializer)?;
+    if value == "0" || value == 0 {
+        return Ok(Color::Black);
+    }
+    Color::deserialize(value).map_err(de::Error::custom)
+}
+
+#[derive(Deserialize)]
+pub struct Indicator {
+    #[serde(deserialize_with = "indicator_color")]
+    color: Color,
+    // milliseconds on and off
+    blink: Option<u64>,
+    // milliseconds to fade out and in again
+    fade: Option<u64>,
+    #[serde(skip, default = "Instant::now")]
+    since: Instant,
+}
+
+impl Indicator {
+    // None turns the indicator off
+    pub fn parse(value: &str) -> Result<Option<Indicator>, String> {
+        if value.trim().is_empty() {
+            return Ok(None);
+        }
+        let indicator: Indicator = serde_json::from_str(value).map_err(|err| err.to_string())?;
+        match indicator.color {
+            Color::Black => Ok(None),
+            _ => Ok(Some(indicator)),
+        }
+    }
+
+    // `idx` is 0-2
+    pub fn render(&self, frame: &mut Frame, idx: usize) {
+        let elapsed = self.since.elapsed().as_millis() as u64;
+        if let Some(blink) = self.blink.filter(|blink| *blink > 0) {
+            if (elapsed / blink) % 2 == 1 {
+                return;
+            }
+        }
+        let level = match self.fade.filter(|fade| *fade > 0) {
+            Some(fade) => {
+                let phase = (elapsed % fade.saturating_mul(2)) as f32 / fade as f32;
+                (1f32 - phase).abs()
+            }
+            None => 1f32,
+        };
+
+        for (x, y) in INDICATOR_PIXELS[idx].iter() {
+            let (r, g, b) = self.color.to_rgb(0, *x, *y);
+            let scale = |channel: u8| (channel as f32 * level).round() as u8;
+            frame.set_rgb((scale(r), scale(g), scale(b)), *x, *y);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn switches_both_date_apps() {
+        let settings = Settings::parse(r#"{"DAT": false, "TIM": true}"#).unwrap();
+        assert_eq!(
+            settings.apps(),
+            vec![("clock", true), ("calendar", false), ("date", false)]
+        );
+    }
+}
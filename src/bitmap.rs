@@ -78,7 +78,8 @@ impl Bitmap {
             ':' => {
                 vec![vec![0, 1, 0, 1, 0]]
             }
-            'C' | 'c' => {
+            // degree sign and c, used as the celsius unit
+            'c' => {
                 vec![
                     vec![1, 0, 0, 0, 0],
                     vec![0, 0, 1, 1, 1],
@@ -210,6 +211,190 @@ impl Bitmap {
                 ]
             }
 
+            'B' => {
+                vec![
+                    vec![1, 1, 1, 1, 1],
+                    vec![1, 0, 1, 0, 1],
+                    vec![0, 1, 0, 1, 0],
+                ]
+            }
+            'C' => {
+                vec![
+                    vec![1, 1, 1, 1, 1],
+                    vec![1, 0, 0, 0, 1],
+                    vec![1, 0, 0, 0, 1],
+                ]
+            }
+            'D' => {
+                vec![
+                    vec![1, 1, 1, 1, 1],
+                    vec![1, 0, 0, 0, 1],
+                    vec![0, 1, 1, 1, 0],
+                ]
+            }
+            'E' => {
+                vec![
+                    vec![1, 1, 1, 1, 1],
+                    vec![1, 0, 1, 0, 1],
+                    vec![1, 0, 0, 0, 1],
+                ]
+            }
+            'G' => {
+                vec![
+                    vec![1, 1, 1, 1, 1],
+                    vec![1, 0, 0, 0, 1],
+                    vec![1, 0, 1, 1, 1],
+                ]
+            }
+            'H' => {
+                vec![
+                    vec![1, 1, 1, 1, 1],
+                    vec![0, 0, 1, 0, 0],
+                    vec![1, 1, 1, 1, 1],
+                ]
+            }
+            'I' => {
+                vec![
+                    vec![1, 0, 0, 0, 1],
+                    vec![1, 1, 1, 1, 1],
+                    vec![1, 0, 0, 0, 1],
+                ]
+            }
+            'J' => {
+                vec![
+                    vec![0, 0, 0, 1, 0],
+                    vec![0, 0, 0, 0, 1],
+                    vec![1, 1, 1, 1, 0],
+                ]
+            }
+            'K' => {
+                vec![
+                    vec![1, 1, 1, 1, 1],
+                    vec![0, 0, 1, 0, 0],
+                    vec![1, 1, 0, 1, 1],
+                ]
+            }
+            'L' => {
+                vec![
+                    vec![1, 1, 1, 1, 1],
+                    vec![0, 0, 0, 0, 1],
+                    vec![0, 0, 0, 0, 1],
+                ]
+            }
+            'N' => {
+                vec![
+                    vec![1, 1, 1, 1, 1],
+                    vec![0, 1, 0, 0, 0],
+                    vec![0, 0, 1, 0, 0],
+                    vec![1, 1, 1, 1, 1],
+                ]
+            }
+            'O' => {
+                vec![
+                    vec![0, 1, 1, 1, 0],
+                    vec![1, 0, 0, 0, 1],
+                    vec![0, 1, 1, 1, 0],
+                ]
+            }
+            'P' => {
+                vec![
+                    vec![1, 1, 1, 1, 1],
+                    vec![1, 0, 1, 0, 0],
+                    vec![1, 1, 1, 0, 0],
+                ]
+            }
+            'Q' => {
+                vec![
+                    vec![1, 1, 1, 1, 0],
+                    vec![1, 0, 0, 1, 0],
+                    vec![1, 1, 1, 0, 1],
+                ]
+            }
+            'R' => {
+                vec![
+                    vec![1, 1, 1, 1, 1],
+                    vec![1, 0, 1, 0, 0],
+                    vec![0, 1, 0, 1, 1],
+                ]
+            }
+            'U' => {
+                vec![
+                    vec![1, 1, 1, 1, 1],
+                    vec![0, 0, 0, 0, 1],
+                    vec![1, 1, 1, 1, 1],
+                ]
+            }
+            'V' => {
+                vec![
+                    vec![1, 1, 1, 1, 0],
+                    vec![0, 0, 0, 0, 1],
+                    vec![1, 1, 1, 1, 0],
+                ]
+            }
+            'X' => {
+                vec![
+                    vec![1, 1, 0, 1, 1],
+                    vec![0, 0, 1, 0, 0],
+                    vec![1, 1, 0, 1, 1],
+                ]
+            }
+            'Y' => {
+                vec![
+                    vec![1, 1, 0, 0, 0],
+                    vec![0, 0, 1, 1, 1],
+                    vec![1, 1, 0, 0, 0],
+                ]
+            }
+            'Z' => {
+                vec![
+                    vec![1, 0, 0, 1, 1],
+                    vec![1, 0, 1, 0, 1],
+                    vec![1, 1, 0, 0, 1],
+                ]
+            }
+            '!' => {
+                vec![vec![1, 1, 1, 0, 1]]
+            }
+            '?' => {
+                vec![
+                    vec![1, 0, 0, 0, 0],
+                    vec![1, 0, 1, 0, 1],
+                    vec![1, 1, 0, 0, 0],
+                ]
+            }
+            '/' => {
+                vec![
+                    vec![0, 0, 0, 1, 1],
+                    vec![0, 0, 1, 0, 0],
+                    vec![1, 1, 0, 0, 0],
+                ]
+            }
+            '+' => {
+                vec![
+                    vec![0, 0, 1, 0, 0],
+                    vec![0, 1, 1, 1, 0],
+                    vec![0, 0, 1, 0, 0],
+                ]
+            }
+            '=' => {
+                vec![
+                    vec![0, 1, 0, 1, 0],
+                    vec![0, 1, 0, 1, 0],
+                    vec![0, 1, 0, 1, 0],
+                ]
+            }
+            ',' => {
+                vec![vec![0, 0, 0, 0, 1], vec![0, 0, 0, 1, 0]]
+            }
+            '\'' => {
+                vec![vec![1, 1, 0, 0, 0]]
+            }
+            '(' => {
+                vec![vec![0, 1, 1, 1, 0], vec![1, 0, 0, 0, 1]]
+            }
+            ')' => {
+                vec![vec![1, 0, 0, 0, 1], vec![0, 1, 1, 1, 0]]
+            }
             '-' => {
                 vec![vec![0, 0, 1, 0, 0]]
             }
@@ -219,10 +404,133 @@ impl Bitmap {
             '.' => {
                 vec![vec![0, 0, 0, 0, 1]]
             }
+            _ if char.is_ascii_lowercase() => {
+                return Bitmap::from_char(char.to_ascii_uppercase());
+            }
             _ => {
                 vec![]
             }
         };
         Bitmap { bits }
     }
+
+    // 8 rows tall digits for the clock without seconds, other characters use the small font
+    pub fn from_big_char(char: char) -> Bitmap {
+        let bits: Vec<Vec<usize>> = match char {
+            '1' => {
+                vec![
+                    vec![0, 0, 1, 0, 0, 0, 0, 1],
+                    vec![0, 1, 0, 0, 0, 0, 0, 1],
+                    vec![1, 1, 1, 1, 1, 1, 1, 1],
+                    vec![0, 0, 0, 0, 0, 0, 0, 1],
+                    vec![0, 0, 0, 0, 0, 0, 0, 1],
+                ]
+            }
+            '2' => {
+                vec![
+                    vec![0, 1, 0, 0, 0, 0, 1, 1],
+                    vec![1, 0, 0, 0, 0, 1, 0, 1],
+                    vec![1, 0, 0, 0, 1, 0, 0, 1],
+                    vec![1, 0, 0, 1, 0, 0, 0, 1],
+                    vec![0, 1, 1, 0, 0, 0, 0, 1],
+                ]
+            }
+            '3' => {
+                vec![
+                    vec![0, 1, 0, 0, 0, 0, 1, 0],
+                    vec![1, 0, 0, 0, 0, 0, 0, 1],
+                    vec![1, 0, 0, 1, 0, 0, 0, 1],
+                    vec![1, 0, 0, 1, 0, 0, 0, 1],
+                    vec![0, 1, 1, 0, 1, 1, 1, 0],
+                ]
+            }
+            '4' => {
+                vec![
+                    vec![0, 0, 0, 1, 1, 0, 0, 0],
+                    vec![0, 0, 1, 0, 1, 0, 0, 0],
+                    vec![0, 1, 0, 0, 1, 0, 0, 0],
+                    vec![1, 1, 1, 1, 1, 1, 1, 1],
+                    vec![0, 0, 0, 0, 1, 0, 0, 0],
+                ]
+            }
+            '5' => {
+                vec![
+                    vec![1, 1, 1, 1, 0, 0, 1, 0],
+                    vec![1, 0, 0, 1, 0, 0, 0, 1],
+                    vec![1, 0, 0, 1, 0, 0, 0, 1],
+                    vec![1, 0, 0, 1, 0, 0, 0, 1],
+                    vec![1, 0, 0, 0, 1, 1, 1, 0],
+                ]
+            }
+            '6' => {
+                vec![
+                    vec![0, 0, 1, 1, 1, 1, 1, 0],
+                    vec![0, 1, 0, 1, 0, 0, 0, 1],
+                    vec![1, 0, 0, 1, 0, 0, 0, 1],
+                    vec![1, 0, 0, 1, 0, 0, 0, 1],
+                    vec![0, 0, 0, 0, 1, 1, 1, 0],
+                ]
+            }
+            '7' => {
+                vec![
+                    vec![1, 0, 0, 0, 0, 0, 0, 0],
+                    vec![1, 0, 0, 0, 0, 0, 1, 1],
+                    vec![1, 0, 0, 0, 1, 1, 0, 0],
+                    vec![1, 0, 1, 1, 0, 0, 0, 0],
+                    vec![1, 1, 0, 0, 0, 0, 0, 0],
+                ]
+            }
+            '8' => {
+                vec![
+                    vec![0, 1, 1, 0, 1, 1, 1, 0],
+                    vec![1, 0, 0, 1, 0, 0, 0, 1],
+                    vec![1, 0, 0, 1, 0, 0, 0, 1],
+                    vec![1, 0, 0, 1, 0, 0, 0, 1],
+                    vec![0, 1, 1, 0, 1, 1, 1, 0],
+                ]
+            }
+            '9' => {
+                vec![
+                    vec![0, 1, 1, 1, 0, 0, 0, 0],
+                    vec![1, 0, 0, 0, 1, 0, 0, 1],
+                    vec![1, 0, 0, 0, 1, 0, 0, 1],
+                    vec![1, 0, 0, 0, 1, 0, 1, 0],
+                    vec![0, 1, 1, 1, 1, 1, 0, 0],
+                ]
+            }
+            '0' => {
+                vec![
+                    vec![0, 1, 1, 1, 1, 1, 1, 0],
+                    vec![1, 0, 0, 0, 0, 0, 0, 1],
+                    vec![1, 0, 0, 0, 0, 0, 0, 1],
+                    vec![1, 0, 0, 0, 0, 0, 0, 1],
+                    vec![0, 1, 1, 1, 1, 1, 1, 0],
+                ]
+            }
+            ':' => {
+                vec![vec![0, 0, 1, 0, 0, 1, 0, 0]]
+            }
+            ' ' => {
+                vec![vec![0]]
+            }
+            _ => {
+                return Bitmap::from_char(char);
+            }
+        };
+        Bitmap { bits }
+    }
+
+    pub fn text_width(text: &str) -> usize {
+        text.chars()
+            .map(|char| Bitmap::from_char(char).bits.len() + 1)
+            .sum::<usize>()
+            .saturating_sub(1)
+    }
+
+    pub fn big_text_width(text: &str) -> usize {
+        text.chars()
+            .map(|char| Bitmap::from_big_char(char).bits.len() + 1)
+            .sum::<usize>()
+            .saturating_sub(1)
+    }
 }
@@ -0,0 +1,53 @@
+use crate::alarm::AlarmConfig;
+use crate::app::calendar::CalendarConfig;
+use crate::app::clock::ClockConfig;
+use crate::app::date::DateConfig;
+use crate::app::world_clock::WorldClockConfig;
+use crate::app::PlaylistEntry;
+use crate::pomodoro::PomodoroConfig;
+use crate::publish::PublishConfig;
+use crate::schedule::{Profile, ScheduleRule};
+use crate::theme::Theme;
+use crate::timer::TimerConfig;
+use chrono_tz::Tz;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    // IANA name like "Europe/London", the system timezone when missing
+    pub timezone: Option<Tz>,
+    pub theme: Theme,
+    pub clock: ClockConfig,
+    pub date: DateConfig,
+    pub calendar: CalendarConfig,
+    pub world_clock: WorldClockConfig,
+    pub timer: TimerConfig,
+    pub alarm: AlarmConfig,
+    pub pomodoro: PomodoroConfig,
+    pub publish: PublishConfig,
+    pub playlist: Option<Vec<PlaylistEntry>>,
+    pub profiles: HashMap<String, Profile>,
+    pub schedule: Vec<ScheduleRule>,
+}
+
+impl Config {
+    pub fn load() -> Config {
+        let path = match std::env::var("CONFIG_PATH") {
+            Ok(path) => path,
+            Err(_) => return Config::default(),
+        };
+
+        let config = std::fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|content| serde_json::from_str(&content).map_err(|err| err.to_string()));
+        match config {
+            Ok(config) => config,
+            Err(err) => {
+                println!("Cannot load config {}: {}, using defaults", path, err);
+                Config::default()
+            }
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use crate::renderer::Color;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+#[derive(Deserialize)]
+pub struct Rgb {
+    r: u8,
+    g: u8,
+    b: u8,
+}
+
+// a command of the JSON schema light
+#[derive(Deserialize)]
+pub struct LightCommand {
+    // "ON" or "OFF"
+    pub state: Option<String>,
+    // 0-255
+    pub brightness: Option<u8>,
+    color: Option<Rgb>,
+}
+
+impl LightCommand {
+    pub fn parse(value: &str) -> Result<LightCommand, String> {
+        serde_json::from_str(value).map_err(|err| err.to_string())
+    }
+
+    pub fn power(&self) -> Result<Option<bool>, String> {
+        match self.state.as_deref() {
+            Some("ON") => Ok(Some(true)),
+            Some("OFF") => Ok(Some(false)),
+            Some(state) => Err(format!("unknown state '{}'", state)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn color(&self) -> Option<Color> {
+        self.color
+            .as_ref()
+            .map(|color| Color::from_rgb8(color.r, color.g, color.b))
+    }
+}
+
+pub fn light_state(power: bool, brightness: u8, color: &Color) -> String {
+    let (r, g, b) = color.to_rgb(0, 0, 0);
+    json!({
+        "state": if power { "ON" } else { "OFF" },
+        "brightness": brightness,
+        "color_mode": "rgb",
+        "color": { "r": r, "g": g, "b": b },
+    })
+    .to_string()
+}
+
+// the unit and device class of each published reading
+const SENSORS: [(&str, &str, &str, &str); 4] = [
+    ("temperature", "Temperature", "temperature", "°C"),
+    ("humidity", "Humidity", "humidity", "%"),
+    ("pressure", "Pressure", "pressure", "hPa"),
+    ("lux", "Light", "illuminance", "lx"),
+];
+
+// the retained discovery configs as topic and payload, `prefix` is the MQTT_TOPIC_PREFIX,
+// `apps` the options of the app select and `json` whether readings are published as JSON
+pub fn discovery(
+    discovery_prefix: &str,
+    prefix: &str,
+    apps: Vec<String>,
+    json: bool,
+) -> Vec<(String, String)> {
+    let node = prefix.replace('/', "_");
+    let device = json!({
+        "identifiers": [node],
+        "name": format!("RPi Clock {}", prefix),
+        "model": "rpi-clock",
+        "sw_version": env!("CARGO_PKG_VERSION"),
+    });
+    let config = |component: &str, object: &str, mut payload: Value| {
+        payload["unique_id"] = json!(format!("{}_{}", node, object));
+        payload["device"] = device.clone();
+        payload["availability_topic"] = json!(format!("{}/status", prefix));
+        (
+            format!(
+                "{}/{}/{}/{}/config",
+                discovery_prefix, component, node, object
+            ),
+            payload.to_string(),
+        )
+    };
+
+    let mut configs = Vec::new();
+    for (metric, name, device_class, unit) in SENSORS.iter() {
+        let mut payload = json!({
+            "name": name,
+            "state_topic": format!("{}/{}", prefix, metric),
+            "device_class": device_class,
+            "unit_of_measurement": unit,
+            "state_class": "measurement",
+        });
+        if json {
+            payload["value_template"] = json!("{{ value_json.value }}");
+        }
+        configs.push(config("sensor", metric, payload));
+    }
+
+    configs.push(config(
+        "light",
+        "matrix",
+        json!({
+            "name": "Matrix",
+            "schema": "json",
+            "command_topic": format!("{}/light/set", prefix),
+            "state_topic": format!("{}/light/state", prefix),
+            "brightness": true,
+            "supported_color_modes": ["rgb"],
+        }),
+    ));
+    configs.push(config(
+        "select",
+        "app",
+        json!({
+            "name": "App",
+            "command_topic": format!("{}/switch", prefix),
+            "state_topic": format!("{}/stats/currentApp", prefix),
+            "options": apps,
+        }),
+    ));
+    configs.push(config(
+        "text",
+        "notify",
+        json!({
+            "name": "Notification",
+            "command_topic": format!("{}/notify", prefix),
+            "mode": "text",
+            "max": 255,
+        }),
+    ));
+    configs
+}
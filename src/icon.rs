@@ -0,0 +1,61 @@
+use crate::sprite::Sprite;
+use std::collections::HashMap;
+use std::path::Path;
+
+pub const ICON_SIZE: usize = 8;
+
+pub struct IconRegistry {
+    icons: HashMap<String, Sprite>,
+}
+
+impl IconRegistry {
+    pub fn new() -> IconRegistry {
+        IconRegistry {
+            icons: HashMap::new(),
+        }
+    }
+
+    pub fn load_dir(dir: &Path) -> IconRegistry {
+        let mut registry = IconRegistry::new();
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(err) => {
+                println!("Cannot read icon dir {}: {}", dir.display(), err);
+                return registry;
+            }
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+            match Sprite::load(&path) {
+                // apps reserve an 8 pixel column for the icon
+                Ok(sprite) if sprite.width() != ICON_SIZE || sprite.height() != ICON_SIZE => {
+                    println!(
+                        "Skip icon {}: {}x{} is not {}x{}",
+                        path.display(),
+                        sprite.width(),
+                        sprite.height(),
+                        ICON_SIZE,
+                        ICON_SIZE
+                    )
+                }
+                Ok(sprite) => registry.insert(name, sprite),
+                Err(err) => println!("Skip icon {}: {}", path.display(), err),
+            }
+        }
+        println!("Loaded {} icons", registry.icons.len());
+        registry
+    }
+
+    pub fn insert(&mut self, name: String, sprite: Sprite) {
+        self.icons.insert(name, sprite);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Sprite> {
+        self.icons.get(name)
+    }
+}
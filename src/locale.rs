@@ -0,0 +1,269 @@
+use chrono::{DateTime, Datelike, FixedOffset};
+use serde::Deserialize;
+
+// names are ASCII only, the font has no accented letters
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    En,
+    De,
+    Fr,
+    Es,
+    It,
+    Nl,
+    Pt,
+}
+
+impl Default for Locale {
+    fn default() -> Locale {
+        Locale::En
+    }
+}
+
+struct Names {
+    weekdays: [&'static str; 7],
+    weekdays_short: [&'static str; 7],
+    months: [&'static str; 12],
+    months_short: [&'static str; 12],
+}
+
+// weekdays start on monday
+const EN: Names = Names {
+    weekdays: [
+        "MONDAY",
+        "TUESDAY",
+        "WEDNESDAY",
+        "THURSDAY",
+        "FRIDAY",
+        "SATURDAY",
+        "SUNDAY",
+    ],
+    weekdays_short: ["MON", "TUE", "WED", "THU", "FRI", "SAT", "SUN"],
+    months: [
+        "JANUARY",
+        "FEBRUARY",
+        "MARCH",
+        "APRIL",
+        "MAY",
+        "JUNE",
+        "JULY",
+        "AUGUST",
+        "SEPTEMBER",
+        "OCTOBER",
+        "NOVEMBER",
+        "DECEMBER",
+    ],
+    months_short: [
+        "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+    ],
+};
+
+const DE: Names = Names {
+    weekdays: [
+        "MONTAG",
+        "DIENSTAG",
+        "MITTWOCH",
+        "DONNERSTAG",
+        "FREITAG",
+        "SAMSTAG",
+        "SONNTAG",
+    ],
+    weekdays_short: ["MO", "DI", "MI", "DO", "FR", "SA", "SO"],
+    months: [
+        "JANUAR",
+        "FEBRUAR",
+        "MAERZ",
+        "APRIL",
+        "MAI",
+        "JUNI",
+        "JULI",
+        "AUGUST",
+        "SEPTEMBER",
+        "OKTOBER",
+        "NOVEMBER",
+        "DEZEMBER",
+    ],
+    months_short: [
+        "JAN", "FEB", "MRZ", "APR", "MAI", "JUN", "JUL", "AUG", "SEP", "OKT", "NOV", "DEZ",
+    ],
+};
+
+const FR: Names = Names {
+    weekdays: [
+        "LUNDI", "MARDI", "MERCREDI", "JEUDI", "VENDREDI", "SAMEDI", "DIMANCHE",
+    ],
+    weekdays_short: ["LUN", "MAR", "MER", "JEU", "VEN", "SAM", "DIM"],
+    months: [
+        "JANVIER",
+        "FEVRIER",
+        "MARS",
+        "AVRIL",
+        "MAI",
+        "JUIN",
+        "JUILLET",
+        "AOUT",
+        "SEPTEMBRE",
+        "OCTOBRE",
+        "NOVEMBRE",
+        "DECEMBRE",
+    ],
+    months_short: [
+        "JAN", "FEV", "MAR", "AVR", "MAI", "JUN", "JUL", "AOU", "SEP", "OCT", "NOV", "DEC",
+    ],
+};
+
+const ES: Names = Names {
+    weekdays: [
+        "LUNES",
+        "MARTES",
+        "MIERCOLES",
+        "JUEVES",
+        "VIERNES",
+        "SABADO",
+        "DOMINGO",
+    ],
+    weekdays_short: ["LUN", "MAR", "MIE", "JUE", "VIE", "SAB", "DOM"],
+    months: [
+        "ENERO",
+        "FEBRERO",
+        "MARZO",
+        "ABRIL",
+        "MAYO",
+        "JUNIO",
+        "JULIO",
+        "AGOSTO",
+        "SEPTIEMBRE",
+        "OCTUBRE",
+        "NOVIEMBRE",
+        "DICIEMBRE",
+    ],
+    months_short: [
+        "ENE", "FEB", "MAR", "ABR", "MAY", "JUN", "JUL", "AGO", "SEP", "OCT", "NOV", "DIC",
+    ],
+};
+
+const IT: Names = Names {
+    weekdays: [
+        "LUNEDI",
+        "MARTEDI",
+        "MERCOLEDI",
+        "GIOVEDI",
+        "VENERDI",
+        "SABATO",
+        "DOMENICA",
+    ],
+    weekdays_short: ["LUN", "MAR", "MER", "GIO", "VEN", "SAB", "DOM"],
+    months: [
+        "GENNAIO",
+        "FEBBRAIO",
+        "MARZO",
+        "APRILE",
+        "MAGGIO",
+        "GIUGNO",
+        "LUGLIO",
+        "AGOSTO",
+        "SETTEMBRE",
+        "OTTOBRE",
+        "NOVEMBRE",
+        "DICEMBRE",
+    ],
+    months_short: [
+        "GEN", "FEB", "MAR", "APR", "MAG", "GIU", "LUG", "AGO", "SET", "OTT", "NOV", "DIC",
+    ],
+};
+
+const NL: Names = Names {
+    weekdays: [
+        "MAANDAG",
+        "DINSDAG",
+        "WOENSDAG",
+        "DONDERDAG",
+        "VRIJDAG",
+        "ZATERDAG",
+        "ZONDAG",
+    ],
+    weekdays_short: ["MA", "DI", "WO", "DO", "VR", "ZA", "ZO"],
+    months: [
+        "JANUARI",
+        "FEBRUARI",
+        "MAART",
+        "APRIL",
+        "MEI",
+        "JUNI",
+        "JULI",
+        "AUGUSTUS",
+        "SEPTEMBER",
+        "OKTOBER",
+        "NOVEMBER",
+        "DECEMBER",
+    ],
+    months_short: [
+        "JAN", "FEB", "MRT", "APR", "MEI", "JUN", "JUL", "AUG", "SEP", "OKT", "NOV", "DEC",
+    ],
+};
+
+const PT: Names = Names {
+    weekdays: [
+        "SEGUNDA", "TERCA", "QUARTA", "QUINTA", "SEXTA", "SABADO", "DOMINGO",
+    ],
+    weekdays_short: ["SEG", "TER", "QUA", "QUI", "SEX", "SAB", "DOM"],
+    months: [
+        "JANEIRO",
+        "FEVEREIRO",
+        "MARCO",
+        "ABRIL",
+        "MAIO",
+        "JUNHO",
+        "JULHO",
+        "AGOSTO",
+        "SETEMBRO",
+        "OUTUBRO",
+        "NOVEMBRO",
+        "DEZEMBRO",
+    ],
+    months_short: [
+        "JAN", "FEV", "MAR", "ABR", "MAI", "JUN", "JUL", "AGO", "SET", "OUT", "NOV", "DEZ",
+    ],
+};
+
+impl Locale {
+    fn names(&self) -> &'static Names {
+        match self {
+            Locale::En => &EN,
+            Locale::De => &DE,
+            Locale::Fr => &FR,
+            Locale::Es => &ES,
+            Locale::It => &IT,
+            Locale::Nl => &NL,
+            Locale::Pt => &PT,
+        }
+    }
+
+    // replaces the weekday and month names in a chrono format before chrono formats the rest
+    pub fn format(&self, format: &str, date: &DateTime<FixedOffset>) -> String {
+        let names = self.names();
+        let weekday = date.weekday().num_days_from_monday() as usize;
+        let month = date.month0() as usize;
+
+        let mut localized = String::with_capacity(format.len());
+        let mut chars = format.chars();
+        while let Some(char) = chars.next() {
+            if char != '%' {
+                localized.push(char);
+                continue;
+            }
+            match chars.next() {
+                Some('a') => localized.push_str(names.weekdays_short[weekday]),
+                Some('A') => localized.push_str(names.weekdays[weekday]),
+                Some('b') | Some('h') => localized.push_str(names.months_short[month]),
+                Some('B') => localized.push_str(names.months[month]),
+                Some(next) => {
+                    localized.push('%');
+                    localized.push(next);
+                }
+                None => localized.push('%'),
+            }
+        }
+        date.format(&localized).to_string()
+    }
+}
@@ -1,197 +1,344 @@
-use crate::renderer::{Color, Frame};
+use crate::config::Config;
+use crate::mqtt::Mqtt;
+use crate::renderer::Frame;
 use crate::sensor::Sensor;
-use chrono::Local;
+use crate::state::RenderState;
+use nix::sys::signal::{SigSet, Signal};
 use rppal::spi::{Bus, Mode, SlaveSelect, Spi};
 use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::RecvTimeoutError;
 use std::sync::{Arc, RwLock};
-use std::time::Duration;
-use std::time::SystemTime;
+use std::time::{Duration, Instant};
 
+mod alarm;
+mod api;
+mod app;
+mod awtrix;
 mod bitmap;
+mod config;
+mod home_assistant;
+mod icon;
+mod locale;
 mod mqtt;
+mod named_color;
+mod notification;
+mod pomodoro;
+mod publish;
 mod renderer;
+mod schedule;
 mod sensor;
+mod sprite;
+mod state;
+mod stopwatch;
+mod theme;
+mod timer;
 
-struct RenderState {
-    state: State,
-    remain_tick: usize,
-    temperature: Option<f32>,
-    humidity: Option<f32>,
-    brightness: f32,
-    last_update: Option<SystemTime>,
-    color: Color,
-}
-
-enum State {
-    Clock,
-    Date,
-    Temperature,
-    Humidity,
-    Empty,
-}
-
-impl RenderState {
-    fn init() -> RenderState {
-        RenderState {
-            state: State::Empty,
-            remain_tick: 0,
-            temperature: None,
-            humidity: None,
-            brightness: 0.1f32,
-            last_update: None,
-            color: Color::RGB,
-        }
-    }
-
-    fn format_time() -> String {
-        let now = Local::now();
-        if now.timestamp_subsec_millis() < 500 {
-            now.format("%H:%M:%S").to_string()
-        } else {
-            now.format("%H %M %S").to_string()
-        }
-    }
-
-    fn format_date() -> String {
-        let now = Local::now();
-        now.format("%m-%d_%a").to_string()
-    }
-
-    fn next(&mut self) {
-        if self.remain_tick > 0 {
-            self.remain_tick -= 1;
-            return;
-        }
-
-        let (next_state, next_tick_remain) = match self.state {
-            State::Clock => (State::Date, 1),
-            State::Date => {
-                if self.is_temperature_humidity_stale() {
-                    (State::Clock, 5)
-                } else {
-                    (State::Temperature, 1)
-                }
-            }
-            State::Temperature => {
-                if self.is_temperature_humidity_stale() {
-                    (State::Clock, 5)
-                } else {
-                    (State::Humidity, 1)
-                }
-            }
-            State::Humidity => (State::Clock, 15),
-            State::Empty => (State::Clock, 10),
-        };
-
-        self.state = next_state;
-        self.remain_tick = next_tick_remain;
-    }
-
-    fn set_temperature(&mut self, value: &str) {
-        if let Ok(value) = value.parse() {
-            self.temperature = Some(value);
-            self.last_update = Some(SystemTime::now());
-        }
-    }
-
-    fn set_humidity(&mut self, value: &str) {
-        if let Ok(value) = value.parse() {
-            self.humidity = Some(value);
-            self.last_update = Some(SystemTime::now());
-        }
-    }
-
-    fn set_color(&mut self, value: &str) {
-        if let Ok(value) = value.parse() {
-            self.color = value;
-            self.last_update = Some(SystemTime::now());
-        }
-    }
+const STATS_INTERVAL: Duration = Duration::from_secs(10);
 
-    fn set_brightness(&mut self, brightness: f32) {
-        self.brightness = brightness;
-    }
-
-    fn get_state(&self) -> &State {
-        &self.state
-    }
-
-    fn get_render_text(&self) -> String {
-        match self.state {
-            State::Clock => RenderState::format_time(),
-            State::Date => RenderState::format_date(),
-            State::Temperature => self
-                .temperature
-                .as_ref()
-                .and_then(|value| Some(format!("{:.2}c", value)))
-                .unwrap_or("".to_string()),
-            State::Humidity => self
-                .humidity
-                .as_ref()
-                .and_then(|value| Some(format!("{:.2}%", value)))
-                .unwrap_or("".to_string()),
-            State::Empty => "".to_string(),
-        }
-    }
-
-    fn is_temperature_humidity_stale(&self) -> bool {
-        match self.last_update {
-            Some(last_update) => last_update.elapsed().unwrap().as_secs() > 300,
-            None => true,
+// publishes the discovery configs and the retained states after connecting,
+// returns the playlist apps offered by the app select
+fn announce(mqtt: &mut Mqtt, state: &RwLock<RenderState>, json: bool) -> Vec<String> {
+    let (apps, light, app) = match state.read() {
+        Ok(state) => (
+            state.playlist_apps(),
+            state.light_state(),
+            state.current_app(),
+        ),
+        Err(_) => return Vec::new(),
+    };
+    if let Ok(discovery_prefix) = std::env::var("HA_DISCOVERY_PREFIX") {
+        let configs =
+            home_assistant::discovery(&discovery_prefix, mqtt.prefix(), apps.clone(), json);
+        for (topic, payload) in configs {
+            mqtt.publish_to(&topic, payload, true);
         }
     }
-
-    fn is_temperature_humidity_just_updated(&self) -> bool {
-        match self.last_update {
-            Some(last_update) => last_update.elapsed().unwrap().as_secs() < 1,
-            None => false,
-        }
+    mqtt.publish("light/state", light, true);
+    if let Some(app) = app {
+        mqtt.publish("stats/currentApp", app, true);
     }
+    apps
 }
 
 fn main() {
     println!("Started");
-    let state = Arc::new(RwLock::new(RenderState::init()));
+    // blocked before any thread starts so only the signal thread receives them
+    let mut signals = SigSet::empty();
+    signals.add(Signal::SIGINT);
+    signals.add(Signal::SIGTERM);
+    signals.thread_block().unwrap();
+    let running = Arc::new(AtomicBool::new(true));
+    let running_signal = running.clone();
+    std::thread::spawn(move || {
+        if let Ok(signal) = signals.wait() {
+            println!("Received {}, stopping", signal);
+        }
+        running_signal.store(false, Ordering::SeqCst);
+    });
+
+    let config = Config::load();
+    let mut publisher = publish::Publisher::new(config.publish.clone());
+    let state = Arc::new(RwLock::new(RenderState::init(config)));
     let state_read = state.clone();
     let adps_reading = sensor::apds_9960::ApdsSensor::init("/dev/i2c-1".to_string());
+    let bme_reading = sensor::bme280::Bme280Sensor::init("/dev/i2c-1".to_string());
+
+    // gestures are polled more often than the tick to feel responsive
+    let state_gesture = state.clone();
+    let adps_gesture = adps_reading.clone();
+    std::thread::spawn(move || loop {
+        let gestures = match adps_gesture.write() {
+            Ok(mut reading) => match reading.as_mut() {
+                Some(reading) => reading.take_gestures(),
+                None => Vec::new(),
+            },
+            Err(_) => Vec::new(),
+        };
+        if !gestures.is_empty() {
+            if let Ok(mut state) = state_gesture.write() {
+                for gesture in gestures {
+                    (*state).gesture(gesture);
+                }
+            }
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    });
 
-    if let Ok(mut mqtt) = mqtt::Mqtt::connect() {
+    let mut mqtt_thread = None;
+    if let Ok(mut mqtt) = mqtt::Mqtt::new() {
         let state_mqtt = state.clone();
+        let running_mqtt = running.clone();
         // move the mqtt to new thread to prevent it to be dropped
-        std::thread::spawn(move || {
+        mqtt_thread = Some(std::thread::spawn(move || {
             let mqtt_channel = mqtt.consume();
             mqtt.subscribe("temperature");
             mqtt.subscribe("humidity");
             mqtt.subscribe("color");
+            mqtt.subscribe("theme");
+            mqtt.subscribe("playlist");
+            mqtt.subscribe("gif");
+            mqtt.subscribe("notify");
+            mqtt.subscribe("custom/+");
 
-            mqtt_channel.iter().for_each(|msg| {
-                if let Some(msg) = msg {
-                    let topic = msg.topic();
-                    if let Ok(mut state) = state_mqtt.write() {
-                        if topic.contains("temperature") {
-                            (*state).set_temperature(&msg.payload_str());
-                        } else if topic.contains("humidity") {
-                            (*state).set_humidity(&msg.payload_str());
-                        } else if topic.contains("color") {
-                            (*state).set_color(&msg.payload_str());
+            mqtt.subscribe("notify/dismiss");
+            mqtt.subscribe("settings");
+            mqtt.subscribe("power");
+            mqtt.subscribe("indicator1");
+            mqtt.subscribe("indicator2");
+            mqtt.subscribe("indicator3");
+            mqtt.subscribe("timer");
+            mqtt.subscribe("stopwatch");
+            mqtt.subscribe("alarm");
+            mqtt.subscribe("pomodoro");
+            mqtt.subscribe("light/set");
+            mqtt.subscribe("switch");
+
+            if !mqtt.connect(&running_mqtt) {
+                return;
+            }
+            if let Ok(mut state) = state_mqtt.write() {
+                (*state).mqtt_connected = true;
+            }
+            let mut announced = announce(&mut mqtt, &state_mqtt, publisher.is_json());
+
+            let mut last_stats = Instant::now();
+            while running_mqtt.load(Ordering::SeqCst) {
+                match mqtt_channel.recv_timeout(Duration::from_secs(1)) {
+                    Ok(Some(msg)) => {
+                        // below the prefix, e.g. "custom/energy"
+                        let topic = msg.topic();
+                        let topic = topic
+                            .strip_prefix(mqtt.prefix())
+                            .and_then(|topic| topic.strip_prefix('/'))
+                            .unwrap_or(topic);
+                        if topic == "gif" {
+                            match state::decode_animation(msg.payload()) {
+                                Ok(animation) => {
+                                    if let Ok(mut state) = state_mqtt.write() {
+                                        (*state).set_animation(animation);
+                                    }
+                                }
+                                Err(err) => println!("Cannot decode gif: {}", err),
+                            }
+                        } else if let Ok(mut state) = state_mqtt.write() {
+                            let payload = msg.payload_str();
+                            if let Some(name) = topic.strip_prefix("custom/") {
+                                // the error topic is matched by the same subscription
+                                if name != "error" {
+                                    if let Err(err) = (*state).set_custom_app(name, &payload) {
+                                        println!("Invalid custom app {}: {}", name, err);
+                                        mqtt.publish("custom/error", err, false);
+                                    }
+                                }
+                            } else {
+                                match topic {
+                                    "timer" => {
+                                        if let Err(err) = (*state).timer(&payload) {
+                                            println!("Invalid timer: {}", err);
+                                            mqtt.publish("timer/error", err, false);
+                                        }
+                                    }
+                                    "stopwatch" => {
+                                        if let Err(err) = (*state).stopwatch(&payload) {
+                                            println!("Invalid stopwatch command: {}", err);
+                                            mqtt.publish("stopwatch/error", err, false);
+                                        }
+                                    }
+                                    "alarm" => {
+                                        if let Err(err) = (*state).alarm(&payload) {
+                                            println!("Invalid alarm: {}", err);
+                                            mqtt.publish("alarm/error", err, false);
+                                        }
+                                    }
+                                    "pomodoro" => {
+                                        if let Err(err) = (*state).pomodoro(&payload) {
+                                            println!("Invalid pomodoro command: {}", err);
+                                            mqtt.publish("pomodoro/error", err, false);
+                                        }
+                                    }
+                                    "light/set" => {
+                                        if let Err(err) = (*state).set_light(&payload) {
+                                            println!("Invalid light command: {}", err);
+                                            mqtt.publish("light/error", err, false);
+                                        }
+                                    }
+                                    "switch" => {
+                                        if let Err(err) = (*state).switch_app(&payload) {
+                                            println!("Invalid switch: {}", err);
+                                            mqtt.publish("switch/error", err, false);
+                                        }
+                                    }
+                                    "notify/dismiss" => (*state).dismiss_notification(),
+                                    // the local BME280 publishes to these topics itself
+                                    "temperature" | "humidity" if state.bme280.is_some() => {}
+                                    "temperature" => (*state).set_temperature(&payload),
+                                    "humidity" => (*state).set_humidity(&payload),
+                                    "color" => {
+                                        if let Err(err) = (*state).set_color(&payload) {
+                                            println!("Invalid color: {}", err);
+                                            mqtt.publish("color/error", err.to_string(), false);
+                                        }
+                                    }
+                                    "theme" => {
+                                        if let Err(err) = (*state).set_theme(&payload) {
+                                            println!("Invalid theme: {}", err);
+                                            mqtt.publish("theme/error", err.to_string(), false);
+                                        }
+                                    }
+                                    "playlist" => {
+                                        if let Err(err) = (*state).set_playlist(&payload) {
+                                            println!("Invalid playlist: {}", err);
+                                            mqtt.publish("playlist/error", err, false);
+                                        }
+                                    }
+                                    "notify" => {
+                                        if let Err(err) = (*state).notify(&payload) {
+                                            println!("Invalid notification: {}", err);
+                                            mqtt.publish("notify/error", err, false);
+                                        }
+                                    }
+                                    "settings" => {
+                                        if let Err(err) = (*state).set_settings(&payload) {
+                                            println!("Invalid settings: {}", err);
+                                            mqtt.publish("settings/error", err, false);
+                                        }
+                                    }
+                                    "power" => {
+                                        if let Err(err) = (*state).set_power(&payload) {
+                                            println!("Invalid power: {}", err);
+                                            mqtt.publish("power/error", err, false);
+                                        }
+                                    }
+                                    "indicator1" | "indicator2" | "indicator3" => {
+                                        let number = topic["indicator".len()..]
+                                            .parse::<usize>()
+                                            .unwrap_or(0);
+                                        if let Err(err) = (*state).set_indicator(number, &payload) {
+                                            println!("Invalid indicator: {}", err);
+                                            mqtt.publish("indicator/error", err, false);
+                                        }
+                                    }
+                                    _ => {}
+                                }
+                            }
+                        }
+                    }
+                    // the connection was lost
+                    Ok(None) => {
+                        println!("Disconnected");
+                        if let Ok(mut state) = state_mqtt.write() {
+                            (*state).mqtt_connected = false;
+                        }
+                        if !mqtt.connect(&running_mqtt) {
+                            break;
+                        }
+                        if let Ok(mut state) = state_mqtt.write() {
+                            (*state).mqtt_connected = true;
                         }
+                        announced = announce(&mut mqtt, &state_mqtt, publisher.is_json());
                     }
-                } else {
-                    println!("None message");
-                    mqtt.reconnect();
-                    mqtt.subscribe("temperature");
-                    mqtt.subscribe("humidity");
+                    Err(RecvTimeoutError::Timeout) => {}
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+
+                let events = match state_mqtt.write() {
+                    Ok(mut state) => (*state).take_events(),
+                    Err(_) => Vec::new(),
+                };
+                for event in events {
+                    mqtt.publish(&event.topic, event.payload, event.retained);
                 }
-            })
-        });
+
+                // the app select follows playlist changes, e.g. a new custom app
+                let apps = match state_mqtt.read() {
+                    Ok(state) => state.playlist_apps(),
+                    Err(_) => Vec::new(),
+                };
+                if apps != announced {
+                    announced = announce(&mut mqtt, &state_mqtt, publisher.is_json());
+                }
+
+                // AWTRIX publishes its stats periodically
+                if last_stats.elapsed() >= STATS_INTERVAL {
+                    last_stats = Instant::now();
+                    if let Ok(state) = state_mqtt.read() {
+                        mqtt.publish("stats", state.stats(), false);
+                    }
+                }
+
+                let readings = match state_mqtt.read() {
+                    Ok(state) => publisher.due(&state.readings()),
+                    Err(_) => Vec::new(),
+                };
+                for (topic, payload) in readings {
+                    mqtt.publish(&topic, payload, publisher.is_retained());
+                }
+            }
+            mqtt.disconnect();
+        }));
     };
 
+    if let Some(port) = std::env::var("API_PORT")
+        .ok()
+        .and_then(|val| val.parse::<u16>().ok())
+    {
+        let state_api = state.clone();
+        std::thread::spawn(move || api::serve(port, state_api));
+    }
+
     std::thread::spawn(move || loop {
         if let Ok(mut state) = state.write() {
             (*state).next();
             if let Ok(reading) = adps_reading.read() {
                 if let Some(reading) = reading.deref() {
-                    (*state).set_brightness(reading.get_light())
+                    (*state).set_ambient_light(reading.get_light(), reading.get_lux())
+                }
+            }
+            // taken so only new readings count as an update
+            if let Ok(mut reading) = bme_reading.write() {
+                if let Some(reading) = reading.take() {
+                    (*state).set_sensor_reading(reading);
                 }
             }
         }
@@ -202,27 +349,20 @@ fn main() {
 
     let mut spi = Spi::new(Bus::Spi0, SlaveSelect::Ss0, 15_600_000, Mode::Mode0).unwrap();
     let mut frame = Frame::new();
-    loop {
+    while running.load(Ordering::SeqCst) {
         frame.clear();
         if let Ok(state) = state_read.read() {
-            frame.set_brightness(state.brightness);
-            match state.get_state() {
-                State::Clock => frame.draw_text(&state.get_render_text(), &state.color, 2, 1),
-                State::Date => frame.draw_text(&state.get_render_text(), &state.color, 1, 1),
-                State::Temperature => frame.draw_text(&state.get_render_text(), &state.color, 6, 1),
-                State::Humidity => frame.draw_text(&state.get_render_text(), &state.color, 6, 1),
-                _ => {}
-            }
-
-            if state.is_temperature_humidity_stale() {
-                frame.draw_pixel(&Color::Raw(64f32, 0f32, 0f32), 1, 7);
-            }
-
-            if state.is_temperature_humidity_just_updated() {
-                frame.draw_pixel(&Color::RGB, 30, 7);
-            }
+            state.render(&mut frame);
         }
         spi.write(&frame.get_spi_data()).unwrap();
         std::thread::sleep(Duration::from_micros(1_000_000 / 60));
     }
+
+    // the panel keeps showing the last frame otherwise
+    frame.clear();
+    spi.write(&frame.get_spi_data()).unwrap();
+    if let Some(mqtt_thread) = mqtt_thread {
+        let _ = mqtt_thread.join();
+    }
+    println!("Stopped");
 }
@@ -1,12 +1,79 @@
 use mqtt::Message;
 use paho_mqtt as mqtt;
 use std::env::VarError;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::Receiver;
 use std::time::Duration;
 
+// the retained availability topic below the prefix, "online" or "offline"
+const STATUS_TOPIC: &str = "status";
+const KEEP_ALIVE: Duration = Duration::from_secs(20);
+// seconds the broker keeps the subscriptions and queued messages while the clock is away
+const SESSION_EXPIRY: i32 = 3600;
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+// certificates are PEM files
+struct Tls {
+    ca_file: Option<String>,
+    client_cert: Option<String>,
+    client_key: Option<String>,
+    verify_hostname: bool,
+}
+
+impl Tls {
+    // from `ssl://` or `mqtts://` in MQTT_HOST, MQTT_TLS or a CA file
+    fn from_env(host: &str) -> Option<Tls> {
+        let ca_file = std::env::var("MQTT_CA_FILE").ok();
+        let enabled = host.starts_with("ssl://")
+            || host.starts_with("mqtts://")
+            || env_flag("MQTT_TLS", false)
+            || ca_file.is_some();
+        if !enabled {
+            return None;
+        }
+        Some(Tls {
+            ca_file,
+            client_cert: std::env::var("MQTT_CLIENT_CERT").ok(),
+            client_key: std::env::var("MQTT_CLIENT_KEY").ok(),
+            verify_hostname: env_flag("MQTT_TLS_VERIFY_HOSTNAME", true),
+        })
+    }
+
+    fn ssl_options(&self) -> Result<mqtt::SslOptions, MqttError> {
+        let mut builder = mqtt::SslOptionsBuilder::new();
+        // the system CAs are used without a CA file
+        if let Some(ca_file) = &self.ca_file {
+            builder.trust_store(ca_file)?;
+        }
+        if let Some(client_cert) = &self.client_cert {
+            builder.key_store(client_cert)?;
+        }
+        if let Some(client_key) = &self.client_key {
+            builder.private_key(client_key)?;
+        }
+        builder
+            .enable_server_cert_auth(true)
+            .verify(self.verify_hostname);
+        Ok(builder.finalize())
+    }
+}
+
+fn env_flag(name: &str, default: bool) -> bool {
+    match std::env::var(name) {
+        Ok(value) => matches!(value.trim(), "1" | "true" | "on" | "yes"),
+        Err(_) => default,
+    }
+}
+
 pub struct Mqtt {
     client: mqtt::Client,
     topic_prefix: String,
+    username: String,
+    password: String,
+    tls: Option<Tls>,
+    // restored on every connect
+    subscriptions: Vec<String>,
 }
 
 #[derive(Debug)]
@@ -30,64 +97,183 @@ impl From<mqtt::Error> for MqttError {
     }
 }
 
+// sleeps in short steps to notice a shutdown, false when it was stopped
+fn wait(duration: Duration, running: &AtomicBool) -> bool {
+    let step = Duration::from_millis(200);
+    let mut waited = Duration::from_secs(0);
+    while waited < duration {
+        if !running.load(Ordering::SeqCst) {
+            return false;
+        }
+        std::thread::sleep(step);
+        waited += step;
+    }
+    running.load(Ordering::SeqCst)
+}
+
 impl Mqtt {
-    pub fn connect() -> Result<Mqtt, MqttError> {
+    // creates the client from the environment, `connect` opens the connection
+    pub fn new() -> Result<Mqtt, MqttError> {
         let host = std::env::var("MQTT_HOST")?;
         let port = std::env::var("MQTT_PORT")?;
         let username = std::env::var("MQTT_USERNAME")?;
         let password = std::env::var("MQTT_PASSWORD")?;
         let topic_prefix = std::env::var("MQTT_TOPIC_PREFIX")?;
+        // a fixed id lets the broker resume the session
+        let client_id = std::env::var("MQTT_CLIENT_ID")
+            .unwrap_or_else(|_| format!("rpi-clock-{}", topic_prefix.replace('/', "-")));
 
-        let connection_str = format!("tcp://{}:{}", host, port);
+        let tls = Tls::from_env(&host);
+        let host = ["ssl://", "mqtts://", "tcp://", "mqtt://"]
+            .iter()
+            .find_map(|scheme| host.strip_prefix(scheme))
+            .unwrap_or(&host)
+            .to_string();
+        let scheme = if tls.is_some() { "ssl" } else { "tcp" };
+        let connection_str = format!("{}://{}:{}", scheme, host, port);
         let client_opt = mqtt::CreateOptionsBuilder::new()
             .mqtt_version(mqtt::MQTT_VERSION_5)
             .server_uri(connection_str)
+            .client_id(client_id)
             .finalize();
 
-        let client = mqtt::client::Client::new(client_opt).unwrap();
-
-        client.connect(
-            mqtt::ConnectOptionsBuilder::new()
-                .mqtt_version(mqtt::MQTT_VERSION_5)
-                .user_name(username)
-                .password(password)
-                //.keep_alive_interval(Duration::from_secs(20))
-                //.automatic_reconnect(Duration::from_secs(1), Duration::from_secs(8192))
-                //.clean_start(false)
-                .connect_timeout(Duration::from_secs(5))
-                .finalize(),
-        )?;
-
-        Ok(Mqtt {
+        let client = mqtt::client::Client::new(client_opt)?;
+
+        let mqtt = Mqtt {
             client,
             topic_prefix,
-        })
+            username,
+            password,
+            tls,
+            subscriptions: Vec::new(),
+        };
+        // reports missing certificates now rather than on every connect
+        if let Err(err) = mqtt.connect_options() {
+            println!("Invalid MQTT TLS options {:?}", err);
+            return Err(err);
+        }
+        Ok(mqtt)
     }
 
-    pub fn reconnect(&mut self) {
-        println!("Reconnecting");
-        if let Err(msg) = self.client.reconnect() {
-            println!("Reconnect fail {}, sleep 5s and try", msg.to_string());
-            std::thread::sleep(Duration::from_secs(5));
-            self.reconnect();
-        } else {
-            println!("Reconnected");
+    fn connect_options(&self) -> Result<mqtt::ConnectOptions, MqttError> {
+        // the broker publishes this when the connection drops without a disconnect
+        let will = mqtt::Message::new_retained(
+            format!("{}/{}", self.topic_prefix, STATUS_TOPIC),
+            "offline",
+            1,
+        );
+        let mut properties = mqtt::Properties::new();
+        let _ = properties.push_int(mqtt::PropertyCode::SessionExpiryInterval, SESSION_EXPIRY);
+
+        // reconnecting is left to `connect` so the subscriptions are restored
+        let mut builder = mqtt::ConnectOptionsBuilder::new();
+        builder
+            .mqtt_version(mqtt::MQTT_VERSION_5)
+            .user_name(self.username.clone())
+            .password(self.password.clone())
+            .keep_alive_interval(KEEP_ALIVE)
+            .clean_start(false)
+            .properties(properties)
+            .connect_timeout(Duration::from_secs(5))
+            .will_message(will);
+        if let Some(tls) = &self.tls {
+            builder.ssl_options(tls.ssl_options()?);
+        }
+        Ok(builder.finalize())
+    }
+
+    // retries with an exponential backoff until connected, false when stopped before that
+    pub fn connect(&mut self, running: &AtomicBool) -> bool {
+        let mut backoff = MIN_BACKOFF;
+        while running.load(Ordering::SeqCst) {
+            println!("Connecting");
+            let options = match self.connect_options() {
+                Ok(options) => options,
+                Err(err) => {
+                    println!("Invalid connect options {:?}", err);
+                    return false;
+                }
+            };
+            match self.client.connect(options) {
+                Ok(_) => {
+                    println!("Connected");
+                    self.publish_status("online");
+                    for topic in self.subscriptions.clone() {
+                        let _ = self.client.subscribe(&topic, 1);
+                    }
+                    return true;
+                }
+                Err(err) => {
+                    println!(
+                        "Connect fail {}, retrying in {}s",
+                        err.to_string(),
+                        backoff.as_secs()
+                    );
+                    if !wait(backoff, running) {
+                        break;
+                    }
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                }
+            }
+        }
+        false
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.client.is_connected()
+    }
+
+    fn publish_status(&mut self, status: &str) {
+        let topic = format!("{}/{}", self.topic_prefix, STATUS_TOPIC);
+        let _ = self
+            .client
+            .publish(mqtt::Message::new_retained(topic, status, 1));
+    }
+
+    // marks the clock offline before leaving, the will is only sent on a dropped connection
+    pub fn disconnect(&mut self) {
+        if !self.is_connected() {
+            return;
+        }
+        self.publish_status("offline");
+        if let Err(err) = self.client.disconnect(None) {
+            println!("Disconnect fail {}", err.to_string());
         }
     }
 
+    // call before `connect` to not miss messages
     pub fn consume(&mut self) -> Receiver<Option<Message>> {
         self.client.start_consuming()
     }
 
-    pub fn publish(&mut self, topic: &str, message: String) {
+    pub fn prefix(&self) -> &str {
+        &self.topic_prefix
+    }
+
+    // to a topic below the prefix
+    pub fn publish(&mut self, topic: &str, message: String, retained: bool) {
         let topic = format!("{}/{}", self.topic_prefix, topic);
-        let _ = self
-            .client
-            .publish(mqtt::Message::new_retained(topic, message, 0));
+        self.publish_to(&topic, message, retained);
+    }
+
+    // to a topic outside the prefix
+    pub fn publish_to(&mut self, topic: &str, message: String, retained: bool) {
+        let message = if retained {
+            mqtt::Message::new_retained(topic, message, 0)
+        } else {
+            mqtt::Message::new(topic, message, 0)
+        };
+        let _ = self.client.publish(message);
     }
 
+    // remembered for every reconnect, subscribed right away when connected
     pub fn subscribe(&mut self, topic: &str) {
         let topic = format!("{}/{}", self.topic_prefix, topic);
-        let _ = self.client.subscribe(&topic, 1);
+        if !self.subscriptions.contains(&topic) {
+            self.subscriptions.push(topic.clone());
+        }
+        if self.is_connected() {
+            let _ = self.client.subscribe(&topic, 1);
+        }
     }
 }
@@ -0,0 +1,316 @@
+use crate::app::{draw_progress, draw_scroll_text, scroll_time};
+use crate::renderer::{Color, Frame};
+use crate::state::RenderState;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+
+// beyond this the oldest of the lowest priority is dropped
+const MAX_QUEUED: usize = 20;
+
+fn default_duration() -> u64 {
+    5
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Clone, Deserialize)]
+pub struct Notification {
+    // may be left out when there is an icon
+    #[serde(default)]
+    pub text: String,
+    pub icon: Option<String>,
+    pub color: Option<Color>,
+    #[serde(default)]
+    pub rainbow: bool,
+    // seconds on screen, ignored when the text scrolls and `repeat` is set
+    #[serde(default = "default_duration")]
+    pub duration: u64,
+    // how many times a scrolling text runs through
+    pub repeat: Option<u32>,
+    // higher first, a higher priority interrupts the active notification
+    #[serde(default)]
+    pub priority: i32,
+    // stays on screen until it is dismissed
+    #[serde(default)]
+    pub hold: bool,
+    // false replaces the notification on screen instead of queueing behind it,
+    // unless that one has a higher priority
+    #[serde(default = "default_true")]
+    pub stack: bool,
+    pub progress: Option<u8>,
+    #[serde(alias = "progressC")]
+    pub progress_color: Option<Color>,
+}
+
+impl Notification {
+    pub fn parse(value: &str) -> Result<Notification, String> {
+        if value.trim_start().starts_with('{') {
+            let notification: Notification =
+                serde_json::from_str(value).map_err(|err| err.to_string())?;
+            if notification.text.is_empty() && notification.icon.is_none() {
+                return Err("a notification needs a text or an icon".to_string());
+            }
+            return Ok(notification);
+        }
+        if value.trim().is_empty() {
+            return Err("empty notification".to_string());
+        }
+
+        Ok(Notification {
+            text: value.trim().to_string(),
+            icon: None,
+            color: None,
+            rainbow: false,
+            duration: default_duration(),
+            repeat: None,
+            priority: 0,
+            hold: false,
+            stack: true,
+            progress: None,
+            progress_color: None,
+        })
+    }
+
+    fn display_time(&self, state: &RenderState) -> Duration {
+        match (
+            self.repeat,
+            scroll_time(state, self.icon.as_deref(), &self.text),
+        ) {
+            (Some(repeat), Some(scroll_time)) => scroll_time * repeat.max(1),
+            _ => Duration::from_secs(self.duration),
+        }
+    }
+
+    fn render(&self, state: &RenderState, frame: &mut Frame, elapsed: Duration) {
+        let color = match (self.rainbow, &self.color) {
+            (true, _) => &Color::Rainbow,
+            (false, Some(color)) => color,
+            (false, None) => &state.color,
+        };
+        draw_scroll_text(
+            state,
+            frame,
+            elapsed,
+            self.icon.as_deref(),
+            &self.text.to_uppercase(),
+            color,
+        );
+
+        if let Some(progress) = self.progress {
+            draw_progress(
+                state,
+                frame,
+                self.icon.as_deref(),
+                progress,
+                self.progress_color.as_ref().unwrap_or(color),
+            );
+        }
+    }
+}
+
+struct Active {
+    notification: Notification,
+    since: Instant,
+}
+
+pub struct NotificationQueue {
+    queue: Vec<Notification>,
+    active: Option<Active>,
+}
+
+impl NotificationQueue {
+    pub fn new() -> NotificationQueue {
+        NotificationQueue {
+            queue: Vec::new(),
+            active: None,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active.is_some()
+    }
+
+    pub fn push(&mut self, notification: Notification) {
+        let replace = match &self.active {
+            Some(active) => {
+                !notification.stack && notification.priority >= active.notification.priority
+            }
+            None => true,
+        };
+        if replace {
+            self.active = Some(Active {
+                notification,
+                since: Instant::now(),
+            });
+            return;
+        }
+
+        let interrupt = match &self.active {
+            Some(active) => notification.priority > active.notification.priority,
+            None => true,
+        };
+        if !interrupt {
+            self.enqueue(notification);
+            return;
+        }
+
+        // the interrupted notification is shown again from the start later
+        if let Some(active) = self.active.take() {
+            self.queue.insert(0, active.notification);
+            self.trim();
+        }
+        self.active = Some(Active {
+            notification,
+            since: Instant::now(),
+        });
+    }
+
+    fn enqueue(&mut self, notification: Notification) {
+        self.queue.push(notification);
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        while self.queue.len() > MAX_QUEUED {
+            let oldest_lowest = self
+                .queue
+                .iter()
+                .enumerate()
+                .min_by_key(|(idx, notification)| (notification.priority, *idx))
+                .map(|(idx, _)| idx);
+            if let Some(idx) = oldest_lowest {
+                self.queue.remove(idx);
+            }
+        }
+    }
+
+    pub fn is_finished(&self, state: &RenderState) -> bool {
+        match &self.active {
+            Some(active) => {
+                !active.notification.hold
+                    && active.since.elapsed() >= active.notification.display_time(state)
+            }
+            None => true,
+        }
+    }
+
+    // replaces the active notification with the next queued one, if any
+    pub fn next(&mut self) {
+        self.active = None;
+
+        // highest priority first, oldest first within the same priority
+        let next = self
+            .queue
+            .iter()
+            .enumerate()
+            .max_by_key(|(idx, notification)| (notification.priority, -(*idx as isize)))
+            .map(|(idx, _)| idx);
+        if let Some(idx) = next {
+            self.active = Some(Active {
+                notification: self.queue.remove(idx),
+                since: Instant::now(),
+            });
+        }
+    }
+
+    pub fn render(&self, state: &RenderState, frame: &mut Frame) {
+        if let Some(active) = &self.active {
+            active
+                .notification
+                .render(state, frame, active.since.elapsed());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn notification(text: &str, priority: i32) -> Notification {
+        let mut notification = Notification::parse(text).unwrap();
+        notification.priority = priority;
+        notification
+    }
+
+    fn active(queue: &NotificationQueue) -> Option<&str> {
+        queue
+            .active
+            .as_ref()
+            .map(|active| active.notification.text.as_str())
+    }
+
+    #[test]
+    fn queues_in_order_of_priority() {
+        let mut queue = NotificationQueue::new();
+        queue.push(notification("first", 0));
+        queue.push(notification("low", 0));
+        queue.push(notification("high", 2));
+        queue.push(notification("medium", 1));
+        // a higher priority interrupts the one on screen
+        assert_eq!(active(&queue), Some("high"));
+
+        let mut shown = Vec::new();
+        while let Some(text) = active(&queue) {
+            shown.push(text.to_string());
+            queue.next();
+        }
+        assert_eq!(shown, ["high", "medium", "first", "low"]);
+    }
+
+    #[test]
+    fn keeps_the_active_one_on_equal_priority() {
+        let mut queue = NotificationQueue::new();
+        queue.push(notification("first", 1));
+        queue.push(notification("second", 1));
+        assert_eq!(active(&queue), Some("first"));
+        queue.next();
+        assert_eq!(active(&queue), Some("second"));
+        queue.next();
+        assert_eq!(active(&queue), None);
+    }
+
+    #[test]
+    fn replaces_only_equal_or_lower_priority_without_stack() {
+        let mut queue = NotificationQueue::new();
+        queue.push(notification("first", 1));
+        let mut replace = notification("replace", 1);
+        replace.stack = false;
+        queue.push(replace);
+        assert_eq!(active(&queue), Some("replace"));
+        queue.next();
+        assert_eq!(active(&queue), None);
+
+        queue.push(notification("urgent", 5));
+        let mut low = notification("low", 0);
+        low.stack = false;
+        queue.push(low);
+        assert_eq!(active(&queue), Some("urgent"));
+        queue.next();
+        assert_eq!(active(&queue), Some("low"));
+    }
+
+    #[test]
+    fn drops_the_oldest_lowest_priority_beyond_the_limit() {
+        let mut queue = NotificationQueue::new();
+        queue.push(notification("active", 9));
+        queue.push(notification("important", 1));
+        for idx in 0..MAX_QUEUED {
+            queue.push(notification(&format!("chatty {}", idx), 0));
+        }
+        assert_eq!(queue.queue.len(), MAX_QUEUED);
+        queue.next();
+        assert_eq!(active(&queue), Some("important"));
+        queue.next();
+        assert_eq!(active(&queue), Some("chatty 1"));
+    }
+
+    #[test]
+    fn accepts_icon_only_notifications() {
+        let notification = Notification::parse(r#"{"icon": "bell"}"#).unwrap();
+        assert_eq!(notification.text, "");
+        assert_eq!(notification.icon.as_deref(), Some("bell"));
+        assert!(Notification::parse(r#"{"duration": 3}"#).is_err());
+    }
+}
@@ -0,0 +1,237 @@
+use crate::app::draw_flash;
+use crate::renderer::{Color, Frame};
+use crate::state::RenderState;
+use crate::timer::Timer;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+
+// the seconds a new phase takes over the screen
+const ALERT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Copy)]
+pub enum Phase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+impl Phase {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Phase::Work => "work",
+            Phase::ShortBreak => "short_break",
+            Phase::LongBreak => "long_break",
+        }
+    }
+
+    // red for work, green for a break
+    pub fn color(&self) -> Color {
+        match self {
+            Phase::Work => Color::from_rgb8(255, 0, 0),
+            Phase::ShortBreak | Phase::LongBreak => Color::from_rgb8(0, 255, 0),
+        }
+    }
+}
+
+fn default_work() -> u64 {
+    25
+}
+
+fn default_short_break() -> u64 {
+    5
+}
+
+fn default_long_break() -> u64 {
+    15
+}
+
+fn default_cycles() -> u32 {
+    4
+}
+
+// lengths in minutes
+#[derive(Clone, Deserialize)]
+pub struct PomodoroConfig {
+    #[serde(default = "default_work")]
+    pub work: u64,
+    #[serde(default = "default_short_break")]
+    pub short_break: u64,
+    #[serde(default = "default_long_break")]
+    pub long_break: u64,
+    // work sessions before a long break
+    #[serde(default = "default_cycles")]
+    pub cycles: u32,
+}
+
+impl Default for PomodoroConfig {
+    fn default() -> PomodoroConfig {
+        PomodoroConfig {
+            work: default_work(),
+            short_break: default_short_break(),
+            long_break: default_long_break(),
+            cycles: default_cycles(),
+        }
+    }
+}
+
+pub struct Session {
+    pub phase: Phase,
+    pub timer: Timer,
+    // work sessions finished so far
+    pub completed: u32,
+}
+
+// what happened to the pomodoro, published as an event
+pub struct PomodoroEvent {
+    pub phase: &'static str,
+    pub event: &'static str,
+    pub completed: u32,
+}
+
+pub struct Pomodoro {
+    config: PomodoroConfig,
+    pub session: Option<Session>,
+    // when the phase last changed by itself
+    changed: Option<Instant>,
+}
+
+impl Pomodoro {
+    pub fn new(config: PomodoroConfig) -> Pomodoro {
+        Pomodoro {
+            config,
+            session: None,
+            changed: None,
+        }
+    }
+
+    pub fn cycles(&self) -> u32 {
+        self.config.cycles.max(1)
+    }
+
+    fn timer(&self, phase: Phase) -> Timer {
+        let minutes = match phase {
+            Phase::Work => self.config.work,
+            Phase::ShortBreak => self.config.short_break,
+            Phase::LongBreak => self.config.long_break,
+        };
+        Timer::new(
+            phase.name().to_string(),
+            Duration::from_secs(minutes.max(1) * 60),
+        )
+    }
+
+    fn event(&self, event: &'static str) -> Option<PomodoroEvent> {
+        self.session.as_ref().map(|session| PomodoroEvent {
+            phase: session.phase.name(),
+            event,
+            completed: session.completed,
+        })
+    }
+
+    // ends the current phase and starts the one after it
+    fn advance(&mut self) -> Vec<PomodoroEvent> {
+        let (phase, completed) = match &self.session {
+            Some(session) => (session.phase, session.completed),
+            None => return Vec::new(),
+        };
+        let (next, completed) = match phase {
+            Phase::Work if (completed + 1) % self.cycles() == 0 => {
+                (Phase::LongBreak, completed + 1)
+            }
+            Phase::Work => (Phase::ShortBreak, completed + 1),
+            Phase::ShortBreak | Phase::LongBreak => (Phase::Work, completed),
+        };
+
+        let mut events = vec![PomodoroEvent {
+            phase: phase.name(),
+            event: "finished",
+            completed,
+        }];
+        self.session = Some(Session {
+            phase: next,
+            timer: self.timer(next),
+            completed,
+        });
+        events.extend(self.event("started"));
+        events
+    }
+
+    // start, pause, resume, toggle, skip or stop
+    pub fn command(&mut self, value: &str) -> Result<Vec<PomodoroEvent>, String> {
+        let running = self
+            .session
+            .as_ref()
+            .map(|session| session.timer.is_running());
+        let command = match (value.trim(), running) {
+            ("toggle", Some(true)) => "pause",
+            ("toggle", _) => "start",
+            (command, _) => command,
+        };
+
+        match (command, &mut self.session) {
+            ("start", None) => {
+                self.session = Some(Session {
+                    phase: Phase::Work,
+                    timer: self.timer(Phase::Work),
+                    completed: 0,
+                });
+                Ok(self.event("started").into_iter().collect())
+            }
+            ("start", Some(session)) | ("resume", Some(session)) => {
+                session.timer.resume();
+                Ok(Vec::new())
+            }
+            ("pause", Some(session)) => {
+                session.timer.pause();
+                Ok(Vec::new())
+            }
+            ("skip", Some(_)) => Ok(self.advance()),
+            ("stop", Some(_)) => {
+                let event = self.event("stopped");
+                self.session = None;
+                self.changed = None;
+                Ok(event.into_iter().collect())
+            }
+            ("resume", None) | ("pause", None) | ("skip", None) | ("stop", None) => {
+                Err("no pomodoro running".to_string())
+            }
+            (command, _) => Err(format!("unknown command '{}'", command)),
+        }
+    }
+
+    // moves on to the next phase when the current one ran out
+    pub fn update(&mut self) -> Vec<PomodoroEvent> {
+        let done = match &self.session {
+            Some(session) => {
+                session.timer.is_running() && session.timer.remaining() == Duration::from_secs(0)
+            }
+            None => false,
+        };
+        if !done {
+            return Vec::new();
+        }
+        self.changed = Some(Instant::now());
+        self.advance()
+    }
+
+    // a new phase takes over the screen for a moment
+    pub fn is_alerting(&self) -> bool {
+        self.session.is_some() && self.changed.map_or(false, |at| at.elapsed() < ALERT)
+    }
+
+    pub fn render_alert(&self, state: &RenderState, frame: &mut Frame) {
+        if let (Some(session), Some(changed)) = (&self.session, self.changed) {
+            let text = match session.phase {
+                Phase::Work => "WORK",
+                Phase::ShortBreak | Phase::LongBreak => "BREAK",
+            };
+            draw_flash(
+                state,
+                frame,
+                changed.elapsed(),
+                text,
+                &session.phase.color(),
+            );
+        }
+    }
+}
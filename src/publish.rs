@@ -0,0 +1,171 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PayloadFormat {
+    // just the value, e.g. 21.53
+    Plain,
+    // {"value": 21.53}
+    Json,
+}
+
+fn default_interval() -> u64 {
+    60
+}
+
+fn default_format() -> PayloadFormat {
+    PayloadFormat::Plain
+}
+
+#[derive(Clone, Deserialize)]
+pub struct PublishConfig {
+    // seconds between publishing a reading, 0 to publish only on changes
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+    // a change of a reading by this much publishes it right away, e.g. {"temperature": 0.5}
+    #[serde(default)]
+    pub thresholds: HashMap<String, f32>,
+    #[serde(default)]
+    pub retained: bool,
+    #[serde(default = "default_format")]
+    pub format: PayloadFormat,
+}
+
+impl Default for PublishConfig {
+    fn default() -> PublishConfig {
+        PublishConfig {
+            interval: default_interval(),
+            thresholds: HashMap::new(),
+            retained: false,
+            format: default_format(),
+        }
+    }
+}
+
+// decides when the local readings go out to <prefix>/<metric>
+pub struct Publisher {
+    config: PublishConfig,
+    // the last published time and value of each metric
+    published: HashMap<&'static str, (Instant, f32)>,
+}
+
+impl Publisher {
+    pub fn new(config: PublishConfig) -> Publisher {
+        Publisher {
+            config,
+            published: HashMap::new(),
+        }
+    }
+
+    pub fn is_retained(&self) -> bool {
+        self.config.retained
+    }
+
+    pub fn is_json(&self) -> bool {
+        match self.config.format {
+            PayloadFormat::Plain => false,
+            PayloadFormat::Json => true,
+        }
+    }
+
+    fn is_due(&self, metric: &str, value: f32) -> bool {
+        let (at, last) = match self.published.get(metric) {
+            Some(published) => published,
+            None => return true,
+        };
+        let interval = Duration::from_secs(self.config.interval);
+        if self.config.interval > 0 && at.elapsed() >= interval {
+            return true;
+        }
+        match self.config.thresholds.get(metric) {
+            Some(threshold) => (value - last).abs() >= *threshold,
+            None => false,
+        }
+    }
+
+    fn payload(&self, value: f32) -> String {
+        match self.config.format {
+            PayloadFormat::Plain => format!("{:.2}", value),
+            PayloadFormat::Json => {
+                let value = (value as f64 * 100f64).round() / 100f64;
+                serde_json::json!({ "value": value }).to_string()
+            }
+        }
+    }
+
+    // the readings to publish now as topic and payload
+    pub fn due(&mut self, readings: &[(&'static str, f32)]) -> Vec<(String, String)> {
+        let mut messages = Vec::new();
+        for (metric, value) in readings.iter() {
+            if self.is_due(metric, *value) {
+                self.published.insert(metric, (Instant::now(), *value));
+                messages.push((metric.to_string(), self.payload(*value)));
+            }
+        }
+        messages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn publisher(interval: u64, thresholds: &[(&str, f32)]) -> Publisher {
+        Publisher::new(PublishConfig {
+            interval,
+            thresholds: thresholds
+                .iter()
+                .map(|(metric, threshold)| (metric.to_string(), *threshold))
+                .collect(),
+            ..PublishConfig::default()
+        })
+    }
+
+    fn topics(messages: Vec<(String, String)>) -> Vec<String> {
+        messages.into_iter().map(|(topic, _)| topic).collect()
+    }
+
+    #[test]
+    fn publishes_changes_beyond_the_threshold() {
+        let mut publisher = publisher(0, &[("temperature", 0.5)]);
+        let first = publisher.due(&[("temperature", 21.0), ("lux", 10.0)]);
+        assert_eq!(
+            first,
+            vec![
+                ("temperature".to_string(), "21.00".to_string()),
+                ("lux".to_string(), "10.00".to_string()),
+            ]
+        );
+        assert!(publisher
+            .due(&[("temperature", 21.4), ("lux", 90.0)])
+            .is_empty());
+        let changed = publisher.due(&[("temperature", 20.5), ("lux", 90.0)]);
+        assert_eq!(topics(changed), vec!["temperature"]);
+    }
+
+    #[test]
+    fn publishes_again_after_the_interval() {
+        let mut publisher = publisher(60, &[]);
+        assert_eq!(
+            topics(publisher.due(&[("humidity", 40.0)])),
+            vec!["humidity"]
+        );
+        assert!(publisher.due(&[("humidity", 45.0)]).is_empty());
+        let at = Instant::now() - Duration::from_secs(60);
+        publisher.published.insert("humidity", (at, 40.0));
+        assert_eq!(
+            topics(publisher.due(&[("humidity", 45.0)])),
+            vec!["humidity"]
+        );
+    }
+
+    #[test]
+    fn formats_json_payloads() {
+        let mut publisher = publisher(60, &[]);
+        publisher.config.format = PayloadFormat::Json;
+        let messages = publisher.due(&[("pressure", 1013.256)]);
+        assert_eq!(messages[0].1, r#"{"value":1013.26}"#);
+    }
+}
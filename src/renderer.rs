@@ -1,13 +1,34 @@
-use std::{ops::Div, str::FromStr};
+use std::str::FromStr;
 
 use crate::bitmap::Bitmap;
+use crate::named_color;
+use crate::sprite::Sprite;
 use bitvec::prelude::*;
 use palette::{Hsv, LinSrgb};
+use serde::{de, Deserialize, Deserializer};
+use std::time::Duration;
 
-const ROWS: usize = 8;
-const COLS: usize = 32;
+pub const ROWS: usize = 8;
+pub const COLS: usize = 32;
+pub const MIN_BRIGHTNESS: f32 = 0.004;
+pub const MAX_BRIGHTNESS: f32 = 0.3;
 
-pub struct ParseColorErr;
+#[derive(Debug)]
+pub struct ParseColorErr {
+    msg: String,
+}
+
+impl ParseColorErr {
+    fn new(msg: String) -> ParseColorErr {
+        ParseColorErr { msg }
+    }
+}
+
+impl std::fmt::Display for ParseColorErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.msg)
+    }
+}
 
 #[derive(Clone)]
 #[allow(unused)]
@@ -18,44 +39,170 @@ pub enum Color {
     RGB,
     Hsv(f32, f32),
     Raw(f32, f32, f32),
+    Gradient(Vec<Color>),
+}
+
+fn parse_component(value: &str, input: &str) -> Result<u8, ParseColorErr> {
+    value.trim().parse::<u8>().map_err(|_| {
+        ParseColorErr::new(format!(
+            "invalid component '{}' in '{}', expected 0-255",
+            value.trim(),
+            input
+        ))
+    })
+}
+
+fn parse_hex(hex: &str, input: &str) -> Result<Color, ParseColorErr> {
+    let digits: Vec<u8> = hex
+        .chars()
+        .map(|c| c.to_digit(16).map(|d| d as u8))
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(|| ParseColorErr::new(format!("invalid hex colour '{}'", input)))?;
+
+    let (r, g, b) = match digits.len() {
+        3 => (digits[0] * 17, digits[1] * 17, digits[2] * 17),
+        6 => (
+            digits[0] * 16 + digits[1],
+            digits[2] * 16 + digits[3],
+            digits[4] * 16 + digits[5],
+        ),
+        _ => {
+            return Err(ParseColorErr::new(format!(
+                "invalid hex colour '{}', expected #rgb or #rrggbb",
+                input
+            )))
+        }
+    };
+    Ok(Color::from_rgb8(r, g, b))
+}
+
+// "name(a,b)" -> ["a", "b"], commas inside nested brackets are kept
+fn parse_args<'a>(s: &'a str, name: &str) -> Option<Vec<&'a str>> {
+    let inner = s.strip_prefix(name)?.trim_start().strip_prefix('(')?;
+    let inner = inner.strip_suffix(')')?;
+
+    let mut args = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (idx, c) in inner.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                args.push(inner[start..idx].trim());
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    args.push(inner[start..].trim());
+    Some(args)
 }
 
 impl FromStr for Color {
     type Err = ParseColorErr;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
+        let s = s.trim();
+        let lower = s.to_ascii_lowercase();
+        match lower.as_str() {
+            "" => Err(ParseColorErr::new("empty colour".to_string())),
             "rgb" => Ok(Color::RGB),
             "rainbow" => Ok(Color::Rainbow),
             "black" => Ok(Color::Black),
+            "white" => Ok(Color::White),
             _ => {
-                let mut arr = s
-                    .split(',')
-                    .into_iter()
-                    .flat_map(|it| it.parse::<f32>())
-                    .map(|it| it / 255f32);
+                if let Some(hex) = lower.strip_prefix('#') {
+                    return parse_hex(hex, s);
+                }
+
+                if let Some(args) = parse_args(&lower, "hsv") {
+                    if args.len() != 2 {
+                        return Err(ParseColorErr::new(format!(
+                            "'{}' expects hsv(hue,saturation)",
+                            s
+                        )));
+                    }
+                    let hue = args[0]
+                        .parse::<f32>()
+                        .ok()
+                        .filter(|hue| (0f32..=360f32).contains(hue));
+                    let sat = args[1]
+                        .parse::<f32>()
+                        .ok()
+                        .filter(|sat| (0f32..=1f32).contains(sat));
+                    return match (hue, sat) {
+                        (Some(hue), Some(sat)) => Ok(Color::Hsv(hue, sat)),
+                        (None, _) => Err(ParseColorErr::new(format!(
+                            "invalid hue '{}' in '{}', expected 0-360",
+                            args[0], s
+                        ))),
+                        (_, None) => Err(ParseColorErr::new(format!(
+                            "invalid saturation '{}' in '{}', expected 0-1",
+                            args[1], s
+                        ))),
+                    };
+                }
 
-                let red: f32 = arr.next().ok_or(ParseColorErr)?;
-                let green: f32 = arr.next().ok_or(ParseColorErr)?;
-                let blue: f32 = arr.next().ok_or(ParseColorErr)?;
+                if let Some(args) = parse_args(&lower, "gradient") {
+                    if args.len() < 2 {
+                        return Err(ParseColorErr::new(format!(
+                            "'{}' expects at least two colours",
+                            s
+                        )));
+                    }
+                    return args
+                        .iter()
+                        .map(|arg| arg.parse::<Color>())
+                        .collect::<Result<Vec<Color>, ParseColorErr>>()
+                        .map(Color::Gradient);
+                }
+
+                if let Some((r, g, b)) = named_color::lookup(&lower) {
+                    return Ok(Color::from_rgb8(r, g, b));
+                }
 
-                Ok(Color::Raw(red, green, blue))
+                let components: Vec<&str> = s.split(',').collect();
+                if components.len() != 3 {
+                    return Err(ParseColorErr::new(format!("unknown colour '{}'", s)));
+                }
+                Ok(Color::from_rgb8(
+                    parse_component(components[0], s)?,
+                    parse_component(components[1], s)?,
+                    parse_component(components[2], s)?,
+                ))
             }
         }
     }
 }
 
-impl Color {
-    fn is_black(&self) -> bool {
-        match self {
-            Color::Black => true,
-            _ => false,
+// AWTRIX payloads send colours as [r, g, b] as well
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ColorValue {
+    Text(String),
+    Rgb([u8; 3]),
+}
+
+impl<'de> Deserialize<'de> for Color {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        match ColorValue::deserialize(deserializer)? {
+            ColorValue::Text(value) => value.parse().map_err(de::Error::custom),
+            ColorValue::Rgb([r, g, b]) => Ok(Color::from_rgb8(r, g, b)),
         }
     }
-    fn to_rgb(&self, brightness: f32, step: usize, x: usize, y: usize) -> (u8, u8, u8) {
-        if brightness <= 0.04f32 && !self.is_black() {
-            return (1, 1, 1);
+}
+
+impl Color {
+    pub fn from_rgb8(r: u8, g: u8, b: u8) -> Color {
+        // the same as the `black` name
+        if (r, g, b) == (0, 0, 0) {
+            return Color::Black;
         }
+        Color::Raw(r as f32 / 255f32, g as f32 / 255f32, b as f32 / 255f32)
+    }
+
+    pub fn to_rgb(&self, step: usize, x: usize, y: usize) -> (u8, u8, u8) {
         let rgb: LinSrgb<f32> = match self {
             Color::White => LinSrgb::from_components((1f32, 1f32, 1f32)),
             Color::Black => LinSrgb::from_components((0f32, 0f32, 0f32)),
@@ -68,7 +215,7 @@ impl Color {
                     row_step = 16f32 - row_step;
                 }
                 let sat = (row_step / 8f32) * 0.4f32 + 0.6f32; // [0.5 - 0.9]
-                LinSrgb::from(Hsv::new(hue, sat, brightness))
+                LinSrgb::from(Hsv::new(hue, sat, 1f32))
             }
             Color::RGB => {
                 let hue_grad = 64f32;
@@ -79,83 +226,64 @@ impl Color {
                     row_step = 16f32 - row_step;
                 }
                 let sat = (row_step / 8f32) * 0.4f32 + 0.6f32; // [0.5 - 0.9]
-                LinSrgb::from(Hsv::new(hue, sat, brightness))
+                LinSrgb::from(Hsv::new(hue, sat, 1f32))
             }
-            Color::Hsv(hue, sat) => LinSrgb::from(Hsv::new(*hue, *sat, brightness)),
+            Color::Hsv(hue, sat) => LinSrgb::from(Hsv::new(*hue, *sat, 1f32)),
             Color::Raw(r, g, b) => LinSrgb::from_components((*r, *g, *b)),
+            Color::Gradient(stops) => {
+                let pos = x as f32 / (COLS - 1) as f32 * (stops.len() - 1) as f32;
+                let idx = (pos as usize).min(stops.len() - 2);
+                let ratio = pos - idx as f32;
+                let (r1, g1, b1) = stops[idx].to_rgb(step, x, y);
+                let (r2, g2, b2) = stops[idx + 1].to_rgb(step, x, y);
+                let mix = |a: u8, b: u8| (a as f32 * (1f32 - ratio) + b as f32 * ratio) / 255f32;
+                LinSrgb::from_components((mix(r1, r2), mix(g1, g2), mix(b1, b2)))
+            }
         };
-        let mut hsv: Hsv = rgb.into();
-        if let Color::Black = &self {
-        } else {
-            hsv.value = brightness;
-        }
-        let rgb: LinSrgb<f32> = hsv.into();
+        // raw colours keep their value, so #404040 stays a dim grey
         rgb.into_format().into_components()
     }
 }
 
-struct Pixel {
-    color: Color,
-}
-
-impl Pixel {
-    fn to_bytes(&self, brightness: f32, step: usize, x: usize, y: usize) -> [u8; 3] {
-        let (r, g, b) = self.color.to_rgb(brightness, step, x, y);
-        [g, r, b]
-    }
-
-    fn set_color(&mut self, color: &Color) {
-        self.color = color.clone();
-    }
-}
-
-impl Default for Pixel {
-    fn default() -> Pixel {
-        Pixel {
-            color: Color::Black,
-        }
-    }
-}
-
+// colours are stored at full value, the brightness is applied when writing out
 pub struct Frame {
-    pixels: [[Pixel; 8]; 32],
+    pixels: [[(u8, u8, u8); ROWS]; COLS],
     brightness: f32,
     step: usize,
 }
 
 impl Frame {
     pub fn new() -> Frame {
-        let pixels: [[Pixel; 8]; 32] = Default::default();
         Frame {
-            pixels,
+            pixels: [[(0, 0, 0); ROWS]; COLS],
             brightness: 0.1f32,
             step: 0,
         }
     }
 
     pub fn set_brightness(&mut self, brightness: f32) {
-        if brightness > 0.3 {
-            self.brightness = 0.3
-        } else if brightness < 0.004 {
-            self.brightness = 0.004
+        if brightness > MAX_BRIGHTNESS {
+            self.brightness = MAX_BRIGHTNESS
+        } else if brightness < MIN_BRIGHTNESS {
+            self.brightness = MIN_BRIGHTNESS
         } else {
             self.brightness = brightness
         }
     }
 
     pub fn clear(&mut self) {
-        for col in self.pixels.iter_mut() {
-            for pixel in col {
-                pixel.set_color(&Color::Black);
-            }
-        }
+        self.pixels = [[(0, 0, 0); ROWS]; COLS];
     }
 
-    fn draw_bitmap(&mut self, bitmap: &Bitmap, color: &Color, x: usize, y: usize) {
+    fn draw_bitmap(&mut self, bitmap: &Bitmap, color: &Color, x: isize, y: usize) {
         for (col_idx, col) in bitmap.bits.iter().enumerate() {
+            let x = x + col_idx as isize;
+            if x < 0 {
+                continue;
+            }
             for (row_idx, bit) in col.iter().enumerate() {
-                if *bit != 0 && col_idx + x < COLS && row_idx + y < ROWS {
-                    self.pixels[col_idx + x][row_idx + y].set_color(color)
+                if *bit != 0 {
+                    self.draw_pixel(color, x as usize, row_idx + y)
                 }
             }
         }
@@ -163,37 +291,108 @@ impl Frame {
 
     pub fn draw_pixel(&mut self, color: &Color, x: usize, y: usize) {
         if x < COLS && y < ROWS {
-            self.pixels[x][y].set_color(color)
+            self.pixels[x][y] = color.to_rgb(self.step, x, y)
+        }
+    }
+
+    pub fn set_rgb(&mut self, rgb: (u8, u8, u8), x: usize, y: usize) {
+        if x < COLS && y < ROWS {
+            self.pixels[x][y] = rgb
+        }
+    }
+
+    pub fn draw_sprite(&mut self, sprite: &Sprite, elapsed: Duration, x: usize, y: usize) {
+        let frame = sprite.frame_at(elapsed);
+        for row_idx in 0..sprite.height() {
+            for col_idx in 0..sprite.width() {
+                if let Some(rgb) = sprite.pixel(frame, col_idx, row_idx) {
+                    self.set_rgb(rgb, col_idx + x, row_idx + y);
+                }
+            }
+        }
+    }
+
+    pub fn fill_rect(&mut self, color: &Color, x: usize, y: usize, width: usize, height: usize) {
+        for col_idx in x..x + width {
+            for row_idx in y..y + height {
+                self.draw_pixel(color, col_idx, row_idx);
+            }
+        }
+    }
+
+    // scales the pixels already drawn in the area, `level` is 0-1
+    pub fn dim_rect(&mut self, level: f32, x: usize, y: usize, width: usize, height: usize) {
+        let scale = |channel: u8| (channel as f32 * level).round() as u8;
+        for col_idx in x..(x + width).min(COLS) {
+            for row_idx in y..(y + height).min(ROWS) {
+                let (r, g, b) = self.pixels[col_idx][row_idx];
+                self.pixels[col_idx][row_idx] = (scale(r), scale(g), scale(b));
+            }
         }
     }
 
-    pub fn draw_text(&mut self, text: &str, color: &Color, x: usize, y: usize) {
+    // `x` can be negative or past the edge, e.g. for scrolling text
+    pub fn draw_text(&mut self, text: &str, color: &Color, x: isize, y: usize) {
+        self.draw_text_with(text, |_| color, x, y)
+    }
+
+    pub fn draw_text_with<'a, F>(&mut self, text: &str, color_of: F, x: isize, y: usize)
+    where
+        F: Fn(char) -> &'a Color,
+    {
+        self.draw_glyphs(text, Bitmap::from_char, color_of, x, y)
+    }
+
+    // in the 8 rows tall digits
+    pub fn draw_big_text_with<'a, F>(&mut self, text: &str, color_of: F, x: isize, y: usize)
+    where
+        F: Fn(char) -> &'a Color,
+    {
+        self.draw_glyphs(text, Bitmap::from_big_char, color_of, x, y)
+    }
+
+    fn draw_glyphs<'a, F>(
+        &mut self,
+        text: &str,
+        glyph: fn(char) -> Bitmap,
+        color_of: F,
+        x: isize,
+        y: usize,
+    ) where
+        F: Fn(char) -> &'a Color,
+    {
         let mut x_offset = x;
         for char in text.chars() {
-            let bitmap = Bitmap::from_char(char);
-            self.draw_bitmap(&bitmap, color, x_offset, y);
-            x_offset += bitmap.bits.len() + 1;
+            if x_offset >= COLS as isize {
+                break;
+            }
+            let bitmap = glyph(char);
+            self.draw_bitmap(&bitmap, color_of(char), x_offset, y);
+            x_offset += bitmap.bits.len() as isize + 1;
+        }
+    }
+
+    fn pixel_to_bytes(&self, (r, g, b): (u8, u8, u8)) -> [u8; 3] {
+        if (r, g, b) == (0, 0, 0) {
+            return [0, 0, 0];
         }
+        // a lit channel stays at least 1 so dim pixels keep their hue instead of going out
+        let scale = |channel: u8| match channel {
+            0 => 0,
+            _ => ((channel as f32 * self.brightness).round() as u8).max(1),
+        };
+        [scale(g), scale(r), scale(b)]
     }
 
     fn to_bytes(&self) -> [u8; 3 * ROWS * COLS] {
         let mut result = [0u8; 3 * ROWS * COLS];
         let mut idx = 0;
         for (col_idx, col) in self.pixels.iter().enumerate() {
-            let mut row_idx: usize = if col_idx % 2 == 1 { 7 } else { 0 };
-            let apply = |pixel: &Pixel| {
-                for channel in pixel
-                    .to_bytes(self.brightness, self.step, col_idx, row_idx)
-                    .iter()
-                {
+            let apply = |pixel: &(u8, u8, u8)| {
+                for channel in self.pixel_to_bytes(*pixel).iter() {
                     result[idx] = *channel;
                     idx += 1;
                 }
-                if col_idx % 2 == 1 {
-                    row_idx -= 1;
-                } else {
-                    row_idx += 1;
-                }
             };
 
             if col_idx % 2 == 1 {
@@ -224,3 +423,74 @@ impl Frame {
         result
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgb(value: &str) -> (u8, u8, u8) {
+        value.parse::<Color>().unwrap().to_rgb(0, 0, 0)
+    }
+
+    #[test]
+    fn parses_names() {
+        assert!(matches!("rgb".parse(), Ok(Color::RGB)));
+        assert!(matches!("Rainbow".parse(), Ok(Color::Rainbow)));
+        assert!(matches!(" black ".parse(), Ok(Color::Black)));
+        assert_eq!(rgb("white"), (255, 255, 255));
+        assert_eq!(rgb("red"), (255, 0, 0));
+        assert_eq!(rgb("Orange"), (255, 165, 0));
+    }
+
+    #[test]
+    fn parses_hex() {
+        assert_eq!(rgb("#f00"), (255, 0, 0));
+        assert_eq!(rgb("#00FF00"), (0, 255, 0));
+        assert!(matches!("#000".parse(), Ok(Color::Black)));
+        assert!("#ff00".parse::<Color>().is_err());
+        assert!("#gg0000".parse::<Color>().is_err());
+    }
+
+    #[test]
+    fn parses_components() {
+        assert_eq!(rgb("0,0,255"), (0, 0, 255));
+        assert_eq!(rgb(" 255, 0 ,0 "), (255, 0, 0));
+        assert!(matches!("0,0,0".parse(), Ok(Color::Black)));
+    }
+
+    #[test]
+    fn keeps_dim_colours() {
+        assert_eq!(rgb("#404040"), (64, 64, 64));
+        assert_eq!(rgb("#010101"), (1, 1, 1));
+        assert_eq!(rgb("gray"), (128, 128, 128));
+        assert_eq!(rgb("darkblue"), (0, 0, 139));
+        assert_eq!(rgb("64,0,0"), (64, 0, 0));
+
+        let mut frame = Frame::new();
+        frame.set_brightness(1f32);
+        frame.draw_pixel(&"maroon".parse().unwrap(), 0, 0);
+        assert_eq!(frame.pixels[0][0], (128, 0, 0));
+        // grb order on the wire, the brightness is capped
+        assert_eq!(frame.pixel_to_bytes(frame.pixels[0][0]), [0, 38, 0]);
+        frame.set_brightness(0f32);
+        assert_eq!(frame.pixel_to_bytes(frame.pixels[0][0]), [0, 1, 0]);
+    }
+
+    #[test]
+    fn rejects_out_of_range_components() {
+        assert!("256,0,0".parse::<Color>().is_err());
+        assert!("-1,0,0".parse::<Color>().is_err());
+        assert!("hsv(361,1)".parse::<Color>().is_err());
+        assert!("hsv(120,1.5)".parse::<Color>().is_err());
+    }
+
+    #[test]
+    fn rejects_trailing_garbage() {
+        assert!("red!".parse::<Color>().is_err());
+        assert!("#ff0000x".parse::<Color>().is_err());
+        assert!("1,2,3,4".parse::<Color>().is_err());
+        assert!("1,2,3x".parse::<Color>().is_err());
+        assert!("hsv(120,1)x".parse::<Color>().is_err());
+        assert!("".parse::<Color>().is_err());
+    }
+}
@@ -0,0 +1,169 @@
+use crate::app::PlaylistEntry;
+use crate::renderer::Color;
+use crate::theme::Theme;
+use chrono::{Datelike, NaiveDateTime, NaiveTime, Weekday};
+use serde::{de, Deserialize, Deserializer, Serializer};
+use std::collections::HashMap;
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub playlist: Option<Vec<PlaylistEntry>>,
+    pub color: Option<Color>,
+    pub theme: Option<Theme>,
+    pub max_brightness: Option<f32>,
+}
+
+pub fn deserialize_time<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveTime, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    NaiveTime::parse_from_str(&value, "%H:%M")
+        .map_err(|_| de::Error::custom(format!("invalid time '{}', expected HH:MM", value)))
+}
+
+pub fn deserialize_days<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<Weekday>, D::Error> {
+    Vec::<String>::deserialize(deserializer)?
+        .iter()
+        .map(|day| {
+            day.parse::<Weekday>()
+                .map_err(|_| de::Error::custom(format!("invalid weekday '{}'", day)))
+        })
+        .collect()
+}
+
+pub fn serialize_time<S: Serializer>(time: &NaiveTime, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&time.format("%H:%M").to_string())
+}
+
+pub fn serialize_days<S: Serializer>(days: &[Weekday], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(days.iter().map(|day| day.to_string()))
+}
+
+// a window from `from` to `to`, it runs over midnight when `to` is before `from`
+#[derive(Clone, Deserialize)]
+pub struct ScheduleRule {
+    pub profile: String,
+    #[serde(deserialize_with = "deserialize_time")]
+    pub from: NaiveTime,
+    #[serde(deserialize_with = "deserialize_time")]
+    pub to: NaiveTime,
+    // the days the window starts on, empty for every day
+    #[serde(default, deserialize_with = "deserialize_days")]
+    pub days: Vec<Weekday>,
+}
+
+impl ScheduleRule {
+    fn runs_on(&self, day: Weekday) -> bool {
+        self.days.is_empty() || self.days.contains(&day)
+    }
+
+    fn matches(&self, now: NaiveDateTime) -> bool {
+        let time = now.time();
+        let today = now.weekday();
+        if self.from <= self.to {
+            self.from <= time && time < self.to && self.runs_on(today)
+        } else {
+            (time >= self.from && self.runs_on(today))
+                || (time < self.to && self.runs_on(today.pred()))
+        }
+    }
+}
+
+pub struct Schedule {
+    profiles: HashMap<String, Profile>,
+    rules: Vec<ScheduleRule>,
+    active: Option<String>,
+    // the settings overridden by the active profile, restored when it ends
+    pub base: Option<Profile>,
+}
+
+impl Schedule {
+    pub fn new(profiles: HashMap<String, Profile>, rules: Vec<ScheduleRule>) -> Schedule {
+        for rule in rules.iter() {
+            if !profiles.contains_key(&rule.profile) {
+                println!("Schedule refers to unknown profile {}", rule.profile);
+            }
+        }
+        Schedule {
+            profiles,
+            rules,
+            active: None,
+            base: None,
+        }
+    }
+
+    pub fn active_profile(&self) -> Option<&Profile> {
+        self.active
+            .as_ref()
+            .and_then(|name| self.profiles.get(name))
+    }
+
+    // returns true when the active profile changed
+    pub fn update(&mut self, now: NaiveDateTime) -> bool {
+        let profile = self
+            .rules
+            .iter()
+            .find(|rule| rule.matches(now) && self.profiles.contains_key(&rule.profile))
+            .map(|rule| rule.profile.clone());
+        if profile == self.active {
+            return false;
+        }
+
+        println!("Schedule profile {:?} -> {:?}", self.active, profile);
+        self.active = profile;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn rule(from: &str, to: &str, days: &[Weekday]) -> ScheduleRule {
+        ScheduleRule {
+            profile: "night".to_string(),
+            from: NaiveTime::parse_from_str(from, "%H:%M").unwrap(),
+            to: NaiveTime::parse_from_str(to, "%H:%M").unwrap(),
+            days: days.to_vec(),
+        }
+    }
+
+    // 2024-01-01 is a Monday
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd(2024, 1, day).and_hms(hour, minute, 0)
+    }
+
+    #[test]
+    fn matches_within_a_day() {
+        let rule = rule("08:00", "17:00", &[]);
+        assert!(!rule.matches(at(1, 7, 59)));
+        assert!(rule.matches(at(1, 8, 0)));
+        assert!(rule.matches(at(1, 16, 59)));
+        assert!(!rule.matches(at(1, 17, 0)));
+    }
+
+    #[test]
+    fn matches_across_midnight() {
+        let rule = rule("22:00", "07:00", &[]);
+        assert!(!rule.matches(at(1, 21, 59)));
+        assert!(rule.matches(at(1, 22, 0)));
+        assert!(rule.matches(at(1, 23, 59)));
+        assert!(rule.matches(at(2, 0, 0)));
+        assert!(rule.matches(at(2, 6, 59)));
+        assert!(!rule.matches(at(2, 7, 0)));
+        assert!(!rule.matches(at(2, 12, 0)));
+    }
+
+    #[test]
+    fn days_are_the_days_the_window_starts_on() {
+        // Friday night into Saturday morning only
+        let rule = rule("22:00", "07:00", &[Weekday::Fri]);
+        assert!(rule.matches(at(5, 23, 0)));
+        assert!(rule.matches(at(6, 6, 0)));
+        assert!(!rule.matches(at(5, 6, 0)));
+        assert!(!rule.matches(at(6, 23, 0)));
+        assert!(!rule.matches(at(7, 6, 0)));
+    }
+}
@@ -1,35 +1,118 @@
 use crate::sensor::Sensor;
-use apds9960::Apds9960;
+use apds9960::{Apds9960, LightData};
 use linux_embedded_hal::I2cdev;
 use nb::block;
 use std::str::FromStr;
 use std::sync::{Arc, RwLock};
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy)]
+pub enum Gesture {
+    Up,
+    Down,
+    Left,
+    Right,
+}
 
 #[derive(Debug)]
 pub struct ApdsReading {
     light: f32,
+    lux: f32,
+    // swipes since they were last taken
+    gestures: Vec<Gesture>,
 }
 
 impl ApdsReading {
     pub fn get_light(&self) -> f32 {
         self.light as f32
     }
+
+    pub fn get_lux(&self) -> f32 {
+        self.lux
+    }
+
+    pub fn take_gestures(&mut self) -> Vec<Gesture> {
+        std::mem::take(&mut self.gestures)
+    }
 }
 
 pub struct ApdsSensor {}
 
+const LIGHT_INTERVAL: Duration = Duration::from_millis(500);
+const GESTURE_INTERVAL: Duration = Duration::from_millis(20);
+// datasets below this on any photodiode are noise
+const GESTURE_MIN_COUNT: u8 = 10;
+// the minimum change of the ratios, in percent, for a swipe
+const GESTURE_SENSITIVITY: i32 = 30;
+
+// the colour channels weighted to the eye's response, as for the TCS3472 with the same diodes
+fn estimate_lux(light: &LightData) -> f32 {
+    let (r, g, b) = (light.red as f32, light.green as f32, light.blue as f32);
+    (-0.32466 * r + 1.57837 * g - 0.73191 * b).max(0f32)
+}
+
 fn init_and_calibrate(apds: &mut Apds9960<I2cdev>, light_sensing_freq: u8) {
     apds.enable().unwrap();
     apds.enable_light().unwrap();
     apds.set_light_integration_time(light_sensing_freq).unwrap();
+    if apds.enable_proximity().is_err()
+        || apds.enable_gesture().is_err()
+        || apds.enable_gesture_mode().is_err()
+    {
+        println!("Cannot enable gestures");
+    }
+}
+
+// the fifo holds (up, down, left, right) datasets, a swipe shifts the ratios between
+// opposite photodiodes from the first to the last dataset
+fn decode_gesture(data: &[u8]) -> Option<Gesture> {
+    let valid: Vec<&[u8]> = data
+        .chunks(4)
+        .filter(|set| set.len() == 4 && set.iter().all(|count| *count > GESTURE_MIN_COUNT))
+        .collect();
+    let (first, last) = (valid.first()?, valid.last()?);
+    let ratio = |a: u8, b: u8| (a as i32 - b as i32) * 100 / (a as i32 + b as i32);
+    let up_down = ratio(last[0], last[1]) - ratio(first[0], first[1]);
+    let left_right = ratio(last[2], last[3]) - ratio(first[2], first[3]);
+
+    if up_down.abs() >= left_right.abs() && up_down.abs() >= GESTURE_SENSITIVITY {
+        Some(if up_down < 0 {
+            Gesture::Up
+        } else {
+            Gesture::Down
+        })
+    } else if left_right.abs() >= GESTURE_SENSITIVITY {
+        Some(if left_right < 0 {
+            Gesture::Left
+        } else {
+            Gesture::Right
+        })
+    } else {
+        None
+    }
+}
+
+// collects the fifo while a hand is in front, the datasets make up one gesture once it has left
+fn read_gesture(apds: &mut Apds9960<I2cdev>, data: &mut Vec<u8>) -> Option<Gesture> {
+    let level = apds.read_gesture_data_level().unwrap_or(0) as usize;
+    if level == 0 {
+        let gesture = decode_gesture(data);
+        data.clear();
+        return gesture;
+    }
+
+    let mut buf = vec![0u8; level * 4];
+    if apds.read_gesture_data(&mut buf).is_ok() {
+        data.extend_from_slice(&buf);
+    }
+    None
 }
 
 impl Sensor<ApdsReading> for ApdsSensor {
     fn init(bus: String) -> Arc<RwLock<Option<ApdsReading>>> {
         let bus = I2cdev::new(bus).unwrap();
         let mut apds = Apds9960::new(bus);
-        let reading = Arc::new(RwLock::new(None));
+        let reading: Arc<RwLock<Option<ApdsReading>>> = Arc::new(RwLock::new(None));
         let reading_clone = reading.clone();
         let light_sensing_freq: u8 = std::env::var("LIGHT_SENSING_FREQ")
             .ok()
@@ -38,16 +121,63 @@ impl Sensor<ApdsReading> for ApdsSensor {
 
         init_and_calibrate(&mut apds, light_sensing_freq);
 
-        std::thread::spawn(move || loop {
-            let light = block!(apds.read_light()).unwrap();
-            if let Ok(mut reading) = reading.write() {
-                *reading = Some(ApdsReading {
-                    light: light.clear as f32 / 3000 as f32,
-                })
+        std::thread::spawn(move || {
+            let mut last_light: Option<Instant> = None;
+            let mut data = Vec::new();
+            loop {
+                if last_light.map_or(true, |at| at.elapsed() >= LIGHT_INTERVAL) {
+                    last_light = Some(Instant::now());
+                    let data = block!(apds.read_light()).unwrap();
+                    let light = data.clear as f32 / 3000 as f32;
+                    let lux = estimate_lux(&data);
+                    if let Ok(mut reading) = reading.write() {
+                        match reading.as_mut() {
+                            Some(reading) => {
+                                reading.light = light;
+                                reading.lux = lux;
+                            }
+                            None => {
+                                *reading = Some(ApdsReading {
+                                    light,
+                                    lux,
+                                    gestures: Vec::new(),
+                                })
+                            }
+                        }
+                    }
+                }
+
+                if let Some(gesture) = read_gesture(&mut apds, &mut data) {
+                    if let Ok(mut reading) = reading.write() {
+                        if let Some(reading) = reading.as_mut() {
+                            reading.gestures.push(gesture);
+                        }
+                    }
+                }
+                std::thread::sleep(GESTURE_INTERVAL)
             }
-            std::thread::sleep(Duration::from_millis(500))
         });
 
         reading_clone
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_swipes_from_the_ratio_shift() {
+        let up = [100, 20, 50, 50, 60, 60, 50, 50, 20, 100, 50, 50];
+        assert!(matches!(decode_gesture(&up), Some(Gesture::Up)));
+        let right = [50, 50, 20, 100, 50, 50, 100, 20];
+        assert!(matches!(decode_gesture(&right), Some(Gesture::Right)));
+    }
+
+    #[test]
+    fn ignores_noise_and_small_shifts() {
+        assert!(decode_gesture(&[]).is_none());
+        assert!(decode_gesture(&[100, 5, 50, 50, 5, 100, 50, 50]).is_none());
+        assert!(decode_gesture(&[50, 45, 50, 50, 45, 50, 50, 50]).is_none());
+    }
+}
@@ -0,0 +1,57 @@
+use crate::sensor::Sensor;
+use ::bme280::BME280;
+use linux_embedded_hal::{Delay, I2cdev};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+const READ_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy)]
+pub struct Bme280Reading {
+    // celsius
+    pub temperature: f32,
+    // percent
+    pub humidity: f32,
+    // hPa
+    pub pressure: f32,
+}
+
+pub struct Bme280Sensor {}
+
+impl Sensor<Bme280Reading> for Bme280Sensor {
+    // holds the latest reading until it is taken, stays empty when the sensor is missing
+    fn init(bus: String) -> Arc<RwLock<Option<Bme280Reading>>> {
+        let reading: Arc<RwLock<Option<Bme280Reading>>> = Arc::new(RwLock::new(None));
+        let reading_clone = reading.clone();
+        let bus = match I2cdev::new(&bus) {
+            Ok(bus) => bus,
+            Err(err) => {
+                println!("Cannot open {} for the BME280: {}", bus, err);
+                return reading_clone;
+            }
+        };
+        let mut bme280 = BME280::new_primary(bus, Delay);
+        if let Err(err) = bme280.init() {
+            println!("Cannot init the BME280: {:?}", err);
+            return reading_clone;
+        }
+
+        std::thread::spawn(move || loop {
+            match bme280.measure() {
+                Ok(measurements) => {
+                    if let Ok(mut reading) = reading.write() {
+                        *reading = Some(Bme280Reading {
+                            temperature: measurements.temperature,
+                            humidity: measurements.humidity,
+                            pressure: measurements.pressure / 100f32,
+                        });
+                    }
+                }
+                Err(err) => println!("Cannot read the BME280: {:?}", err),
+            }
+            std::thread::sleep(READ_INTERVAL);
+        });
+
+        reading_clone
+    }
+}
@@ -1,4 +1,5 @@
 pub mod apds_9960;
+pub mod bme280;
 
 use std::sync::{Arc, RwLock};
 
@@ -0,0 +1,254 @@
+use std::io::Cursor;
+use std::path::Path;
+use std::time::Duration;
+
+// gifs come from MQTT, larger ones would only be scaled down anyway
+const MAX_GIF_SIZE: usize = 64;
+const MAX_GIF_FRAMES: usize = 100;
+
+#[derive(Debug)]
+pub struct SpriteError {
+    msg: String,
+}
+
+impl From<std::io::Error> for SpriteError {
+    fn from(err: std::io::Error) -> SpriteError {
+        SpriteError {
+            msg: err.to_string(),
+        }
+    }
+}
+
+impl From<png::DecodingError> for SpriteError {
+    fn from(err: png::DecodingError) -> SpriteError {
+        SpriteError {
+            msg: err.to_string(),
+        }
+    }
+}
+
+impl From<gif::DecodingError> for SpriteError {
+    fn from(err: gif::DecodingError) -> SpriteError {
+        SpriteError {
+            msg: err.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for SpriteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.msg)
+    }
+}
+
+pub struct SpriteFrame {
+    pixels: Vec<Option<(u8, u8, u8)>>,
+    delay: Duration,
+}
+
+pub struct Sprite {
+    width: usize,
+    height: usize,
+    frames: Vec<SpriteFrame>,
+}
+
+impl Sprite {
+    pub fn load(path: &Path) -> Result<Sprite, SpriteError> {
+        let bytes = std::fs::read(path)?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("png") => Sprite::from_png(&bytes),
+            Some("gif") => Sprite::from_gif(&bytes),
+            _ => Err(SpriteError {
+                msg: format!("unsupported file {}", path.display()),
+            }),
+        }
+    }
+
+    pub fn from_png(bytes: &[u8]) -> Result<Sprite, SpriteError> {
+        let mut decoder = png::Decoder::new(Cursor::new(bytes));
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let (info, mut reader) = decoder.read_info()?;
+        let mut buf = vec![0; info.buffer_size()];
+        reader.next_frame(&mut buf)?;
+
+        let channels = match info.color_type {
+            png::ColorType::Grayscale => 1,
+            png::ColorType::GrayscaleAlpha => 2,
+            png::ColorType::RGB => 3,
+            png::ColorType::RGBA => 4,
+            png::ColorType::Indexed => {
+                return Err(SpriteError {
+                    msg: "indexed png was not expanded".to_string(),
+                })
+            }
+        };
+
+        let width = info.width as usize;
+        let height = info.height as usize;
+        let mut pixels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            let row = &buf[y * info.line_size..];
+            for x in 0..width {
+                let px = &row[x * channels..(x + 1) * channels];
+                let (rgb, alpha) = match channels {
+                    1 => ((px[0], px[0], px[0]), 255),
+                    2 => ((px[0], px[0], px[0]), px[1]),
+                    3 => ((px[0], px[1], px[2]), 255),
+                    _ => ((px[0], px[1], px[2]), px[3]),
+                };
+                pixels.push(if alpha < 128 { None } else { Some(rgb) });
+            }
+        }
+
+        Ok(Sprite {
+            width,
+            height,
+            frames: vec![SpriteFrame {
+                pixels,
+                delay: Duration::from_secs(0),
+            }],
+        })
+    }
+
+    pub fn from_gif(bytes: &[u8]) -> Result<Sprite, SpriteError> {
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+        // keeps every frame inside the canvas checked below
+        options.check_frame_consistency(true);
+        let mut decoder = options.read_info(Cursor::new(bytes))?;
+        let width = decoder.width() as usize;
+        let height = decoder.height() as usize;
+        if width > MAX_GIF_SIZE || height > MAX_GIF_SIZE {
+            return Err(SpriteError {
+                msg: format!(
+                    "gif is {}x{}, at most {}x{} is supported",
+                    width, height, MAX_GIF_SIZE, MAX_GIF_SIZE
+                ),
+            });
+        }
+
+        // gif frames are patches over the previous canvas, so composite them
+        let mut canvas: Vec<Option<(u8, u8, u8)>> = vec![None; width * height];
+        let mut frames = Vec::new();
+        while let Some(frame) = decoder.read_next_frame()? {
+            // the rest of a long gif is dropped
+            if frames.len() >= MAX_GIF_FRAMES {
+                break;
+            }
+            let previous = canvas.clone();
+            for y in 0..frame.height as usize {
+                for x in 0..frame.width as usize {
+                    let idx = (y * frame.width as usize + x) * 4;
+                    let px = &frame.buffer[idx..idx + 4];
+                    let (cx, cy) = (x + frame.left as usize, y + frame.top as usize);
+                    if px[3] >= 128 && cx < width && cy < height {
+                        canvas[cy * width + cx] = Some((px[0], px[1], px[2]));
+                    }
+                }
+            }
+
+            frames.push(SpriteFrame {
+                pixels: canvas.clone(),
+                // delays are in 10ms units, browsers treat tiny delays as 100ms
+                delay: Duration::from_millis(if frame.delay < 2 {
+                    100
+                } else {
+                    frame.delay as u64 * 10
+                }),
+            });
+
+            match frame.dispose {
+                gif::DisposalMethod::Background => {
+                    for y in 0..frame.height as usize {
+                        for x in 0..frame.width as usize {
+                            let (cx, cy) = (x + frame.left as usize, y + frame.top as usize);
+                            if cx < width && cy < height {
+                                canvas[cy * width + cx] = None;
+                            }
+                        }
+                    }
+                }
+                gif::DisposalMethod::Previous => canvas = previous,
+                _ => {}
+            }
+        }
+
+        if frames.is_empty() {
+            return Err(SpriteError {
+                msg: "gif has no frames".to_string(),
+            });
+        }
+
+        Ok(Sprite {
+            width,
+            height,
+            frames,
+        })
+    }
+
+    // nearest neighbour scale until the target is covered, then crop the centre
+    pub fn fit(&self, width: usize, height: usize) -> Sprite {
+        let scale = (width as f32 / self.width as f32).max(height as f32 / self.height as f32);
+        let scaled_width = (self.width as f32 * scale).round() as usize;
+        let scaled_height = (self.height as f32 * scale).round() as usize;
+        let offset_x = scaled_width.saturating_sub(width) / 2;
+        let offset_y = scaled_height.saturating_sub(height) / 2;
+
+        let frames = self
+            .frames
+            .iter()
+            .map(|frame| {
+                let mut pixels = Vec::with_capacity(width * height);
+                for y in 0..height {
+                    for x in 0..width {
+                        let src_x = ((x + offset_x) as f32 / scale) as usize;
+                        let src_y = ((y + offset_y) as f32 / scale) as usize;
+                        pixels.push(self.pixel(frame, src_x, src_y));
+                    }
+                }
+                SpriteFrame {
+                    pixels,
+                    delay: frame.delay,
+                }
+            })
+            .collect();
+
+        Sprite {
+            width,
+            height,
+            frames,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn frame_at(&self, elapsed: Duration) -> &SpriteFrame {
+        let total: Duration = self.frames.iter().map(|frame| frame.delay).sum();
+        if self.frames.len() == 1 || total.as_millis() == 0 {
+            return &self.frames[0];
+        }
+
+        let mut remain = elapsed.as_millis() % total.as_millis();
+        for frame in self.frames.iter() {
+            if remain < frame.delay.as_millis() {
+                return frame;
+            }
+            remain -= frame.delay.as_millis();
+        }
+        &self.frames[0]
+    }
+
+    pub fn pixel(&self, frame: &SpriteFrame, x: usize, y: usize) -> Option<(u8, u8, u8)> {
+        if x < self.width && y < self.height {
+            frame.pixels[y * self.width + x]
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,653 @@
+use crate::alarm::{AlarmCommand, AlarmEvent, Alarms};
+use crate::app::animation::AnimationApp;
+use crate::app::calendar::CalendarApp;
+use crate::app::clock::ClockApp;
+use crate::app::custom::CustomApp;
+use crate::app::date::DateApp;
+use crate::app::humidity::HumidityApp;
+use crate::app::pomodoro::PomodoroApp;
+use crate::app::pressure::PressureApp;
+use crate::app::temperature::TemperatureApp;
+use crate::app::timer::TimerApp;
+use crate::app::world_clock::WorldClockApp;
+use crate::app::{PlaylistEntry, Scheduler};
+use crate::awtrix::{parse_power, parse_switch, Indicator, Settings};
+use crate::config::Config;
+use crate::home_assistant::{light_state, LightCommand};
+use crate::icon::IconRegistry;
+use crate::notification::{Notification, NotificationQueue};
+use crate::pomodoro::{Pomodoro, PomodoroEvent};
+use crate::renderer::{Color, Frame, ParseColorErr, COLS, MAX_BRIGHTNESS, ROWS};
+use crate::schedule::{Profile, Schedule};
+use crate::sensor::apds_9960::Gesture;
+use crate::sensor::bme280::Bme280Reading;
+use crate::sprite::Sprite;
+use crate::stopwatch::Stopwatch;
+use crate::theme::Theme;
+use crate::timer::{Command, Timers};
+use chrono::{DateTime, FixedOffset, Local, Offset, Utc};
+use chrono_tz::Tz;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+// a message for the MQTT thread to publish, the topic is below the prefix
+pub struct Event {
+    pub topic: String,
+    pub payload: String,
+    pub retained: bool,
+}
+
+// events are dropped beyond this when nothing takes them, e.g. without MQTT
+const MAX_EVENTS: usize = 100;
+
+// the payload of the gif topic scaled to the panel, empty clears the animation,
+// done outside of the state lock so decoding does not stall the render loop
+pub fn decode_animation(value: &[u8]) -> Result<Option<Sprite>, String> {
+    if value.is_empty() {
+        return Ok(None);
+    }
+    let sprite = Sprite::from_gif(value).map_err(|err| err.to_string())?;
+    Ok(Some(sprite.fit(COLS, ROWS)))
+}
+
+pub struct RenderState {
+    pub scheduler: Scheduler,
+    pub timezone: Option<Tz>,
+    pub temperature: Option<f32>,
+    pub humidity: Option<f32>,
+    pub pressure: Option<f32>,
+    // the last reading of the local BME280, unlike the values above it never comes from MQTT
+    pub bme280: Option<Bme280Reading>,
+    pub brightness: f32,
+    pub auto_brightness: bool,
+    pub max_brightness: Option<f32>,
+    pub lux: Option<f32>,
+    pub power: bool,
+    pub last_update: Option<SystemTime>,
+    pub color: Color,
+    pub theme: Theme,
+    pub icons: IconRegistry,
+    pub animation: Option<Sprite>,
+    pub schedule: Schedule,
+    pub notifications: NotificationQueue,
+    pub timers: Timers,
+    pub stopwatch: Stopwatch,
+    pub alarms: Alarms,
+    pub pomodoro: Pomodoro,
+    pub events: Vec<Event>,
+    // set by the MQTT thread
+    pub mqtt_connected: bool,
+    pub indicators: [Option<Indicator>; 3],
+    pub started: Instant,
+}
+
+impl RenderState {
+    pub fn init(config: Config) -> RenderState {
+        let mut scheduler = Scheduler::new();
+        scheduler.register(Box::new(ClockApp::new(config.clock)));
+        scheduler.register(Box::new(CalendarApp::new(&config.date, config.calendar)));
+        scheduler.register(Box::new(TemperatureApp));
+        scheduler.register(Box::new(HumidityApp));
+        scheduler.register(Box::new(PressureApp));
+        scheduler.register(Box::new(AnimationApp::new()));
+        scheduler.register(Box::new(WorldClockApp::new(config.world_clock)));
+        scheduler.register(Box::new(TimerApp));
+        scheduler.register(Box::new(PomodoroApp));
+        // the calendar replaces the text date, which stays available for playlists
+        scheduler.register(Box::new(DateApp::new(config.date)));
+        scheduler.set_enabled("date", false);
+        if let Some(playlist) = config.playlist {
+            if let Err(err) = scheduler.set_playlist(playlist) {
+                println!("Invalid playlist in config: {}", err);
+            }
+        }
+
+        RenderState {
+            scheduler,
+            timezone: config.timezone,
+            temperature: None,
+            humidity: None,
+            pressure: None,
+            bme280: None,
+            brightness: 0.1f32,
+            auto_brightness: true,
+            max_brightness: None,
+            lux: None,
+            power: true,
+            last_update: None,
+            color: Color::RGB,
+            theme: config.theme,
+            icons: std::env::var("ICON_DIR")
+                .map(|dir| IconRegistry::load_dir(Path::new(&dir)))
+                .unwrap_or_else(|_| IconRegistry::new()),
+            animation: std::env::var("GIF_PATH").ok().and_then(|path| {
+                match Sprite::load(Path::new(&path)) {
+                    Ok(sprite) => Some(sprite.fit(COLS, ROWS)),
+                    Err(err) => {
+                        println!("Cannot load gif {}: {}", path, err);
+                        None
+                    }
+                }
+            }),
+            schedule: Schedule::new(config.profiles, config.schedule),
+            notifications: NotificationQueue::new(),
+            timers: Timers::new(config.timer),
+            stopwatch: Stopwatch::new(),
+            alarms: Alarms::load(
+                std::env::var("ALARM_PATH").ok().map(PathBuf::from),
+                config.alarm,
+            ),
+            pomodoro: Pomodoro::new(config.pomodoro),
+            events: Vec::new(),
+            mqtt_connected: false,
+            indicators: [None, None, None],
+            started: Instant::now(),
+        }
+    }
+
+    // the local time in the configured timezone, or the system one
+    pub fn now(&self) -> DateTime<FixedOffset> {
+        match self.timezone {
+            Some(timezone) => {
+                let now = Utc::now().with_timezone(&timezone);
+                now.with_timezone(&now.offset().fix())
+            }
+            None => {
+                let now = Local::now();
+                now.with_timezone(&now.offset().fix())
+            }
+        }
+    }
+
+    fn current_profile(&self) -> Profile {
+        Profile {
+            playlist: Some(self.scheduler.playlist().to_vec()),
+            color: Some(self.color.clone()),
+            theme: Some(self.theme.clone()),
+            max_brightness: self.max_brightness,
+        }
+    }
+
+    fn apply_profile(&mut self, profile: Profile) {
+        if let Some(playlist) = profile.playlist {
+            if let Err(err) = self.scheduler.set_playlist(playlist) {
+                println!("Invalid playlist in profile: {}", err);
+            }
+        }
+        if let Some(color) = profile.color {
+            self.color = color;
+        }
+        if let Some(theme) = profile.theme {
+            self.theme = theme;
+        }
+        self.max_brightness = profile.max_brightness;
+    }
+
+    fn apply_schedule(&mut self) {
+        if !self.schedule.update(self.now().naive_local()) {
+            return;
+        }
+
+        if let Some(base) = self.schedule.base.take() {
+            self.apply_profile(base);
+        }
+        if let Some(profile) = self.schedule.active_profile().cloned() {
+            self.schedule.base = Some(self.current_profile());
+            self.apply_profile(profile);
+        }
+    }
+
+    pub fn next(&mut self) {
+        self.apply_schedule();
+        self.scheduler.remove_expired();
+        for name in self.timers.update() {
+            self.emit(
+                "timer/event",
+                serde_json::json!({ "name": name, "event": "finished" }).to_string(),
+            );
+        }
+        let now = self.now().naive_local();
+        for event in self.alarms.update(now) {
+            self.emit_alarm(event);
+        }
+        for event in self.pomodoro.update() {
+            self.emit_pomodoro(event);
+        }
+        if self.alarms.ringing.is_some()
+            || self.timers.preempting().is_some()
+            || self.pomodoro.is_alerting()
+        {
+            self.scheduler.pause();
+            return;
+        }
+
+        if self.notifications.is_active() && self.notifications.is_finished(self) {
+            self.notifications.next();
+        }
+        if self.notifications.is_active() || self.stopwatch.is_active() {
+            self.scheduler.pause();
+            return;
+        }
+        self.scheduler.resume();
+
+        let available = self.scheduler.availability(self);
+        let previous = self.current_app();
+        self.scheduler.next(&available);
+        if self.current_app() != previous {
+            self.emit_current_app();
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        // the sunrise ignores the power and brightness limits to wake you up
+        if let Some(ringing) = &self.alarms.ringing {
+            frame.set_brightness(ringing.brightness().unwrap_or(MAX_BRIGHTNESS));
+            ringing.render(self, frame);
+            return;
+        }
+        if !self.power {
+            return;
+        }
+
+        frame.set_brightness(match self.max_brightness {
+            Some(max_brightness) => self.brightness.min(max_brightness),
+            None => self.brightness,
+        });
+        if let Some(timer) = self.timers.preempting() {
+            timer.render(self, frame);
+        } else if self.pomodoro.is_alerting() {
+            self.pomodoro.render_alert(self, frame);
+        } else if self.notifications.is_active() {
+            self.notifications.render(self, frame);
+        } else if self.stopwatch.is_active() {
+            self.stopwatch.render(self, frame);
+        } else {
+            self.scheduler.render(self, frame);
+        }
+
+        if self.is_temperature_humidity_stale() {
+            frame.draw_pixel(&self.theme.stale, 1, 7);
+        }
+
+        if self.is_temperature_humidity_just_updated() {
+            frame.draw_pixel(&self.theme.updated, 30, 7);
+        }
+
+        for (idx, indicator) in self.indicators.iter().enumerate() {
+            if let Some(indicator) = indicator {
+                indicator.render(frame, idx);
+            }
+        }
+    }
+
+    pub fn set_temperature(&mut self, value: &str) {
+        if let Ok(value) = value.parse() {
+            self.temperature = Some(value);
+            self.last_update = Some(SystemTime::now());
+        }
+    }
+
+    pub fn set_humidity(&mut self, value: &str) {
+        if let Ok(value) = value.parse() {
+            self.humidity = Some(value);
+            self.last_update = Some(SystemTime::now());
+        }
+    }
+
+    // a reading of the local BME280
+    pub fn set_sensor_reading(&mut self, reading: Bme280Reading) {
+        self.temperature = Some(reading.temperature);
+        self.humidity = Some(reading.humidity);
+        self.pressure = Some(reading.pressure);
+        self.bme280 = Some(reading);
+        self.last_update = Some(SystemTime::now());
+    }
+
+    // the readings of the local sensors by metric
+    pub fn readings(&self) -> Vec<(&'static str, f32)> {
+        let mut readings = Vec::new();
+        if let Some(reading) = self.bme280 {
+            readings.push(("temperature", reading.temperature));
+            readings.push(("humidity", reading.humidity));
+            readings.push(("pressure", reading.pressure));
+        }
+        if let Some(lux) = self.lux {
+            readings.push(("lux", lux));
+        }
+        readings
+    }
+
+    pub fn set_color(&mut self, value: &str) -> Result<(), ParseColorErr> {
+        self.color = value.parse()?;
+        self.last_update = Some(SystemTime::now());
+        Ok(())
+    }
+
+    pub fn set_theme(&mut self, value: &str) -> Result<(), serde_json::Error> {
+        self.theme = serde_json::from_str(value)?;
+        Ok(())
+    }
+
+    pub fn set_playlist(&mut self, value: &str) -> Result<(), String> {
+        let playlist = serde_json::from_str(value).map_err(|err| err.to_string())?;
+        self.scheduler.set_playlist(playlist)
+    }
+
+    pub fn notify(&mut self, value: &str) -> Result<(), String> {
+        self.notifications.push(Notification::parse(value)?);
+        Ok(())
+    }
+
+    // an empty payload removes the app
+    pub fn set_custom_app(&mut self, name: &str, value: &str) -> Result<(), String> {
+        if name.is_empty() || name.contains('/') || name == "error" {
+            return Err(format!("invalid app name '{}'", name));
+        }
+        if let Some(app) = self.scheduler.get(name) {
+            if !app.is_custom() {
+                return Err(format!("'{}' is a built-in app", name));
+            }
+        }
+
+        if value.trim().is_empty() {
+            self.scheduler.unregister(name);
+        } else {
+            let app = CustomApp::parse(name, value)?;
+            // a new app stays in the rotation when the active profile ends
+            if self.scheduler.get(name).is_none() {
+                if let Some(playlist) = self
+                    .schedule
+                    .base
+                    .as_mut()
+                    .and_then(|base| base.playlist.as_mut())
+                {
+                    if !playlist.iter().any(|entry| entry.app == name) {
+                        playlist.push(PlaylistEntry::new(name));
+                    }
+                }
+            }
+            self.scheduler.register(Box::new(app));
+        }
+        Ok(())
+    }
+
+    pub fn timer(&mut self, value: &str) -> Result<(), String> {
+        self.timers.command(Command::parse(value)?)
+    }
+
+    // start, stop, toggle, lap or reset
+    pub fn stopwatch(&mut self, value: &str) -> Result<(), String> {
+        match value.trim() {
+            "start" => self.stopwatch.start(),
+            "stop" => self.stopwatch.stop(),
+            "toggle" => self.stopwatch.toggle(),
+            "lap" => self.stopwatch_lap(),
+            "reset" => self.stopwatch.reset(),
+            command => return Err(format!("unknown command '{}'", command)),
+        }
+        Ok(())
+    }
+
+    fn stopwatch_lap(&mut self) {
+        if let Some(lap) = self.stopwatch.lap() {
+            self.emit(
+                "stopwatch/lap",
+                serde_json::json!({
+                    "lap": lap.number,
+                    "time": lap.time.as_secs_f32(),
+                    "split": lap.split.as_secs_f32(),
+                })
+                .to_string(),
+            );
+        }
+    }
+
+    pub fn alarm(&mut self, value: &str) -> Result<(), String> {
+        if let Some(event) = self.alarms.command(AlarmCommand::parse(value)?)? {
+            self.emit_alarm(event);
+        }
+        Ok(())
+    }
+
+    pub fn pomodoro(&mut self, value: &str) -> Result<(), String> {
+        for event in self.pomodoro.command(value)? {
+            self.emit_pomodoro(event);
+        }
+        Ok(())
+    }
+
+    fn emit_pomodoro(&mut self, event: PomodoroEvent) {
+        self.emit(
+            "pomodoro/event",
+            serde_json::json!({
+                "phase": event.phase,
+                "event": event.event,
+                "completed": event.completed,
+            })
+            .to_string(),
+        );
+    }
+
+    fn emit_alarm(&mut self, event: AlarmEvent) {
+        self.emit(
+            "alarm/event",
+            serde_json::json!({ "name": event.name, "event": event.event }).to_string(),
+        );
+    }
+
+    // a ringing alarm snoozes on a swipe down and is dismissed by any other swipe.
+    // while the stopwatch is on screen a swipe sideways starts or stops it, up takes a lap
+    // and down resets it, otherwise sideways shows the previous or next app and up starts it
+    pub fn gesture(&mut self, gesture: Gesture) {
+        if self.alarms.ringing.is_some() {
+            let event = match gesture {
+                Gesture::Down => self.alarms.snooze(),
+                _ => self.alarms.dismiss(),
+            };
+            if let Some(event) = event {
+                self.emit_alarm(event);
+            }
+            return;
+        }
+
+        if self.stopwatch.is_active() {
+            match gesture {
+                Gesture::Left | Gesture::Right => self.stopwatch.toggle(),
+                Gesture::Up => self.stopwatch_lap(),
+                Gesture::Down => self.stopwatch.reset(),
+            }
+            return;
+        }
+
+        match gesture {
+            Gesture::Left | Gesture::Right => {
+                let available = self.scheduler.availability(self);
+                let previous = self.current_app();
+                self.scheduler
+                    .skip(&available, matches!(gesture, Gesture::Left));
+                if self.current_app() != previous {
+                    self.emit_current_app();
+                }
+            }
+            Gesture::Up => self.stopwatch.start(),
+            Gesture::Down => {}
+        }
+    }
+
+    pub fn emit(&mut self, topic: &str, payload: String) {
+        self.push_event(topic, payload, false);
+    }
+
+    // for states, so a new subscriber gets the current one
+    fn emit_retained(&mut self, topic: &str, payload: String) {
+        self.push_event(topic, payload, true);
+    }
+
+    fn push_event(&mut self, topic: &str, payload: String, retained: bool) {
+        if self.events.len() >= MAX_EVENTS {
+            self.events.remove(0);
+        }
+        self.events.push(Event {
+            topic: topic.to_string(),
+            payload,
+            retained,
+        });
+    }
+
+    pub fn take_events(&mut self) -> Vec<Event> {
+        std::mem::take(&mut self.events)
+    }
+
+    // the distinct apps of the playlist in order
+    pub fn playlist_apps(&self) -> Vec<String> {
+        let mut apps: Vec<String> = Vec::new();
+        for entry in self.scheduler.playlist() {
+            if !apps.contains(&entry.app) {
+                apps.push(entry.app.clone());
+            }
+        }
+        apps
+    }
+
+    pub fn current_app(&self) -> Option<String> {
+        self.scheduler.current().map(|app| app.name().to_string())
+    }
+
+    // the AWTRIX topic of the app on screen
+    fn emit_current_app(&mut self) {
+        if let Some(app) = self.current_app() {
+            self.emit_retained("stats/currentApp", app);
+        }
+    }
+
+    // accepts {"name": "clock"} or the plain app name
+    pub fn switch_app(&mut self, value: &str) -> Result<(), String> {
+        self.scheduler.switch_to(&parse_switch(value)?)?;
+        self.emit_current_app();
+        Ok(())
+    }
+
+    // the brightness in 0-255 like AWTRIX
+    fn brightness_level(&self) -> u8 {
+        (self.brightness.min(MAX_BRIGHTNESS) / MAX_BRIGHTNESS * 255f32).round() as u8
+    }
+
+    pub fn light_state(&self) -> String {
+        light_state(self.power, self.brightness_level(), &self.color)
+    }
+
+    fn emit_light_state(&mut self) {
+        let state = self.light_state();
+        self.emit_retained("light/state", state);
+    }
+
+    // the Home Assistant light, a brightness switches the auto brightness off like BRI
+    pub fn set_light(&mut self, value: &str) -> Result<(), String> {
+        let command = LightCommand::parse(value)?;
+        if let Some(power) = command.power()? {
+            self.power = power;
+        }
+        if let Some(brightness) = command.brightness {
+            self.auto_brightness = false;
+            self.brightness = brightness as f32 / 255f32 * MAX_BRIGHTNESS;
+        }
+        if let Some(color) = command.color() {
+            self.color = color;
+        }
+        self.emit_light_state();
+        Ok(())
+    }
+
+    pub fn dismiss_notification(&mut self) {
+        self.notifications.next();
+    }
+
+    pub fn set_settings(&mut self, value: &str) -> Result<(), String> {
+        let settings = Settings::parse(value)?;
+        if let Some(auto_brightness) = settings.auto_brightness {
+            self.auto_brightness = auto_brightness;
+        }
+        if let Some(brightness) = settings.brightness {
+            self.auto_brightness = false;
+            self.brightness = brightness as f32 / 255f32 * MAX_BRIGHTNESS;
+        }
+        if let Some(secs) = settings.app_time {
+            self.scheduler
+                .set_default_duration(Some(Duration::from_secs(secs)));
+        }
+        for (app, enabled) in settings.apps() {
+            self.scheduler.set_enabled(app, enabled);
+        }
+        if let Some(color) = settings.color {
+            self.color = color;
+        }
+        self.emit_light_state();
+        Ok(())
+    }
+
+    pub fn set_power(&mut self, value: &str) -> Result<(), String> {
+        self.power = parse_power(value)?;
+        self.emit_light_state();
+        Ok(())
+    }
+
+    // `number` is 1-3 like the AWTRIX topics
+    pub fn set_indicator(&mut self, number: usize, value: &str) -> Result<(), String> {
+        match number
+            .checked_sub(1)
+            .and_then(|idx| self.indicators.get_mut(idx))
+        {
+            Some(indicator) => {
+                *indicator = Indicator::parse(value)?;
+                Ok(())
+            }
+            None => Err(format!("unknown indicator {}", number)),
+        }
+    }
+
+    // the AWTRIX stats payload
+    pub fn stats(&self) -> String {
+        serde_json::json!({
+            "temp": self.temperature,
+            "hum": self.humidity,
+            "lux": self.lux,
+            "bri": self.brightness_level(),
+            "uptime": self.started.elapsed().as_secs(),
+            "matrix": self.power,
+            "app": self.scheduler.current().map(|app| app.name()),
+            "indicator1": self.indicators[0].is_some(),
+            "indicator2": self.indicators[1].is_some(),
+            "indicator3": self.indicators[2].is_some(),
+            "mqtt": self.mqtt_connected,
+            "version": env!("CARGO_PKG_VERSION"),
+        })
+        .to_string()
+    }
+
+    pub fn set_animation(&mut self, animation: Option<Sprite>) {
+        self.animation = animation;
+    }
+
+    // the reading of the light sensor, ignored while the brightness is set manually
+    // `brightness` is the light as a share of the sensor's range, `lux` its estimate in lux
+    pub fn set_ambient_light(&mut self, brightness: f32, lux: f32) {
+        self.lux = Some(lux);
+        if self.auto_brightness {
+            self.brightness = brightness;
+        }
+    }
+
+    pub fn is_temperature_humidity_stale(&self) -> bool {
+        match self.last_update {
+            Some(last_update) => last_update.elapsed().unwrap().as_secs() > 300,
+            None => true,
+        }
+    }
+
+    pub fn is_temperature_humidity_just_updated(&self) -> bool {
+        match self.last_update {
+            Some(last_update) => last_update.elapsed().unwrap().as_secs() < 1,
+            None => false,
+        }
+    }
+}
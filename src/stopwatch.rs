@@ -0,0 +1,111 @@
+use crate::bitmap::Bitmap;
+use crate::renderer::{Frame, COLS};
+use crate::state::RenderState;
+use std::time::{Duration, Instant};
+
+pub struct Lap {
+    pub number: usize,
+    pub time: Duration,
+    // the time since the previous lap
+    pub split: Duration,
+}
+
+pub struct Stopwatch {
+    // the run time before the last stop
+    run: Duration,
+    // None while stopped
+    started: Option<Instant>,
+    laps: Vec<Duration>,
+}
+
+impl Stopwatch {
+    pub fn new() -> Stopwatch {
+        Stopwatch {
+            run: Duration::from_secs(0),
+            started: None,
+            laps: Vec::new(),
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.run + self.started.map(|at| at.elapsed()).unwrap_or_default()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.started.is_some()
+    }
+
+    // shown until it is reset
+    pub fn is_active(&self) -> bool {
+        self.is_running() || self.run > Duration::from_secs(0)
+    }
+
+    pub fn start(&mut self) {
+        if self.started.is_none() {
+            self.started = Some(Instant::now());
+        }
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(started) = self.started.take() {
+            self.run += started.elapsed();
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        if self.is_running() {
+            self.stop();
+        } else {
+            self.start();
+        }
+    }
+
+    pub fn lap(&mut self) -> Option<Lap> {
+        if !self.is_running() {
+            return None;
+        }
+        let time = self.elapsed();
+        let split = time - self.laps.last().cloned().unwrap_or_default();
+        self.laps.push(time);
+        Some(Lap {
+            number: self.laps.len(),
+            time,
+            split,
+        })
+    }
+
+    pub fn reset(&mut self) {
+        *self = Stopwatch::new();
+    }
+
+    fn text(&self) -> String {
+        let elapsed = self.elapsed();
+        let secs = elapsed.as_secs();
+        if secs >= 3600 {
+            format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+        } else {
+            format!(
+                "{:02}:{:02}.{}",
+                secs / 60,
+                secs % 60,
+                elapsed.subsec_millis() / 100
+            )
+        }
+    }
+
+    // drawn every frame, so the tenths follow the render loop
+    pub fn render(&self, state: &RenderState, frame: &mut Frame) {
+        // blinks while stopped
+        if !self.is_running() && state.now().timestamp_subsec_millis() >= 500 {
+            return;
+        }
+        let text = self.text();
+        let x = COLS.saturating_sub(Bitmap::text_width(&text)) / 2;
+        frame.draw_text_with(
+            &text,
+            |char| state.theme.color_for(char, &state.color),
+            x as isize,
+            1,
+        );
+    }
+}
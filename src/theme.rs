@@ -0,0 +1,148 @@
+use crate::renderer::Color;
+use serde::{de, Deserialize, Deserializer};
+use std::cmp::Ordering;
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    pub digits: Option<Color>,
+    pub colon: Option<Color>,
+    pub unit: Option<Color>,
+    pub stale: Color,
+    pub updated: Color,
+    // the top of the calendar sheet and the day on it
+    pub calendar_sheet: Color,
+    pub calendar_day: Color,
+    pub temperature_scale: Option<ColorScale>,
+    pub humidity_scale: Option<ColorScale>,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScaleMode {
+    Threshold,
+    Continuous,
+}
+
+impl Default for ScaleMode {
+    fn default() -> ScaleMode {
+        ScaleMode::Continuous
+    }
+}
+
+// stops are (value, colour) pairs sorted by value
+#[derive(Clone, Deserialize)]
+pub struct ColorScale {
+    #[serde(default)]
+    pub mode: ScaleMode,
+    #[serde(deserialize_with = "deserialize_stops")]
+    pub stops: Vec<(f32, Color)>,
+}
+
+// sorted here so the config can list the stops in any order
+fn deserialize_stops<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<(f32, Color)>, D::Error> {
+    let mut stops = Vec::<(f32, Color)>::deserialize(deserializer)?;
+    if stops.is_empty() {
+        return Err(de::Error::custom("a colour scale needs at least one stop"));
+    }
+    stops.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+    if let Some(pair) = stops.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+        return Err(de::Error::custom(format!("duplicate stop {}", pair[0].0)));
+    }
+    Ok(stops)
+}
+
+impl ColorScale {
+    pub fn color_at(&self, value: f32) -> Option<Color> {
+        let first = self.stops.first()?;
+        let last = self.stops.last()?;
+        if value <= first.0 {
+            return Some(first.1.clone());
+        }
+        if value >= last.0 {
+            return Some(last.1.clone());
+        }
+
+        let idx = self.stops.iter().rposition(|(stop, _)| *stop <= value)?;
+        let (from_value, from) = &self.stops[idx];
+        let (to_value, to) = &self.stops[idx + 1];
+        match self.mode {
+            ScaleMode::Threshold => Some(from.clone()),
+            ScaleMode::Continuous => {
+                let ratio = (value - from_value) / (to_value - from_value);
+                let (r1, g1, b1) = from.to_rgb(0, 0, 0);
+                let (r2, g2, b2) = to.to_rgb(0, 0, 0);
+                let mix = |a: u8, b: u8| (a as f32 * (1f32 - ratio) + b as f32 * ratio) as u8;
+                Some(Color::from_rgb8(mix(r1, r2), mix(g1, g2), mix(b1, b2)))
+            }
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme {
+            digits: None,
+            colon: None,
+            unit: None,
+            stale: Color::from_rgb8(64, 0, 0),
+            updated: Color::RGB,
+            calendar_sheet: Color::from_rgb8(255, 0, 0),
+            calendar_day: Color::White,
+            temperature_scale: None,
+            humidity_scale: None,
+        }
+    }
+}
+
+impl Theme {
+    // elements without their own colour use the base colour
+    pub fn color_for<'a>(&'a self, char: char, base: &'a Color) -> &'a Color {
+        let color = match char {
+            '0'..='9' => &self.digits,
+            ':' | '-' | '.' | '_' | ' ' => &self.colon,
+            _ => &self.unit,
+        };
+        color.as_ref().unwrap_or(base)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scale(json: &str) -> Result<ColorScale, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    fn rgb_at(scale: &ColorScale, value: f32) -> (u8, u8, u8) {
+        scale.color_at(value).unwrap().to_rgb(0, 0, 0)
+    }
+
+    #[test]
+    fn sorts_the_stops() {
+        let scale = scale(r#"{"stops": [[28, "red"], [16, "blue"], [22, "lime"]]}"#).unwrap();
+        assert_eq!(rgb_at(&scale, 10.0), (0, 0, 255));
+        assert_eq!(rgb_at(&scale, 22.0), (0, 255, 0));
+        assert_eq!(rgb_at(&scale, 25.0), (127, 127, 0));
+        assert_eq!(rgb_at(&scale, 30.0), (255, 0, 0));
+    }
+
+    #[test]
+    fn picks_the_highest_stop_reached_as_threshold() {
+        let scale =
+            scale(r#"{"mode": "threshold", "stops": [[65, "blue"], [0, "orange"], [40, "lime"]]}"#)
+                .unwrap();
+        assert_eq!(rgb_at(&scale, 39.0), (255, 165, 0));
+        assert_eq!(rgb_at(&scale, 40.0), (0, 255, 0));
+        assert_eq!(rgb_at(&scale, 80.0), (0, 0, 255));
+    }
+
+    #[test]
+    fn rejects_empty_and_duplicate_stops() {
+        assert!(scale(r#"{"stops": []}"#).is_err());
+        assert!(scale(r#"{"stops": [[20, "red"], [10, "blue"], [20, "lime"]]}"#).is_err());
+    }
+}
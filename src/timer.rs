@@ -0,0 +1,400 @@
+use crate::app::{draw_flash, draw_progress, draw_scroll_text};
+use crate::renderer::{Color, Frame};
+use crate::state::RenderState;
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+
+// longer timers do not fit on the screen
+const MAX_HOURS: u64 = 100;
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Start,
+    Pause,
+    Resume,
+    Cancel,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DurationValue {
+    Secs(u64),
+    Text(String),
+}
+
+fn default_name() -> String {
+    "timer".to_string()
+}
+
+fn default_action() -> Action {
+    Action::Start
+}
+
+#[derive(Deserialize)]
+pub struct Command {
+    #[serde(default = "default_name")]
+    pub name: String,
+    #[serde(default = "default_action")]
+    pub action: Action,
+    duration: Option<DurationValue>,
+}
+
+// "90", "90s", "9m", "1h30m" or "1:30" (minutes and seconds)
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let too_long = || format!("duration '{}' is too long", value);
+    let mut secs = 0u64;
+    if value.contains(':') {
+        for part in value.split(':') {
+            let part: u64 = part
+                .parse()
+                .map_err(|_| format!("invalid duration '{}'", value))?;
+            secs = secs
+                .checked_mul(60)
+                .and_then(|secs| secs.checked_add(part))
+                .ok_or_else(too_long)?;
+        }
+    } else {
+        let mut number = String::new();
+        for char in value.chars() {
+            match char {
+                '0'..='9' => number.push(char),
+                'h' | 'm' | 's' if !number.is_empty() => {
+                    let unit = match char {
+                        'h' => 3600,
+                        'm' => 60,
+                        _ => 1,
+                    };
+                    let amount = number
+                        .parse::<u64>()
+                        .ok()
+                        .and_then(|number| number.checked_mul(unit))
+                        .ok_or_else(too_long)?;
+                    secs = secs.checked_add(amount).ok_or_else(too_long)?;
+                    number.clear();
+                }
+                _ => return Err(format!("invalid duration '{}'", value)),
+            }
+        }
+        if !number.is_empty() {
+            let amount = number.parse::<u64>().map_err(|_| too_long())?;
+            secs = secs.checked_add(amount).ok_or_else(too_long)?;
+        }
+    }
+    if secs == 0 {
+        return Err(format!("invalid duration '{}'", value));
+    }
+    Ok(Duration::from_secs(secs))
+}
+
+impl Command {
+    // JSON, or plain text like "pasta 9m", "9m" or "pasta pause"
+    pub fn parse(value: &str) -> Result<Command, String> {
+        if value.trim_start().starts_with('{') {
+            return serde_json::from_str(value).map_err(|err| err.to_string());
+        }
+
+        let mut words: Vec<&str> = value.split_whitespace().collect();
+        let last = words
+            .pop()
+            .ok_or_else(|| "empty timer command".to_string())?;
+        let name = if words.is_empty() {
+            default_name()
+        } else {
+            words.join(" ")
+        };
+        let action = match last {
+            "start" => Some(Action::Start),
+            "pause" => Some(Action::Pause),
+            "resume" => Some(Action::Resume),
+            "cancel" => Some(Action::Cancel),
+            _ => None,
+        };
+        Ok(match action {
+            Some(action) => Command {
+                name,
+                action,
+                duration: None,
+            },
+            None => Command {
+                name,
+                action: Action::Start,
+                duration: Some(DurationValue::Text(last.to_string())),
+            },
+        })
+    }
+
+    pub fn duration(&self) -> Result<Option<Duration>, String> {
+        let duration = match &self.duration {
+            Some(DurationValue::Secs(0)) => return Err("invalid duration 0".to_string()),
+            Some(DurationValue::Secs(secs)) => Duration::from_secs(*secs),
+            Some(DurationValue::Text(text)) => parse_duration(text)?,
+            None => return Ok(None),
+        };
+        if duration > Duration::from_secs(MAX_HOURS * 3600) {
+            return Err(format!("a timer runs for at most {}h", MAX_HOURS));
+        }
+        Ok(Some(duration))
+    }
+}
+
+pub struct Timer {
+    pub name: String,
+    total: Duration,
+    // the run time before the last pause
+    run: Duration,
+    // None while paused
+    resumed: Option<Instant>,
+    created: Instant,
+    finished: Option<Instant>,
+}
+
+impl Timer {
+    pub fn new(name: String, total: Duration) -> Timer {
+        Timer {
+            name,
+            total,
+            run: Duration::from_secs(0),
+            resumed: Some(Instant::now()),
+            created: Instant::now(),
+            finished: None,
+        }
+    }
+
+    pub fn remaining(&self) -> Duration {
+        let run = self.run + self.resumed.map(|at| at.elapsed()).unwrap_or_default();
+        self.total.checked_sub(run).unwrap_or_default()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.resumed.is_some() && self.finished.is_none()
+    }
+
+    pub fn pause(&mut self) {
+        if let Some(resumed) = self.resumed.take() {
+            self.run += resumed.elapsed();
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if self.resumed.is_none() {
+            self.resumed = Some(Instant::now());
+        }
+    }
+
+    // the time left, 0-100
+    pub fn progress(&self) -> u8 {
+        (self.remaining().as_secs_f32() / self.total.as_secs_f32() * 100f32).ceil() as u8
+    }
+
+    pub fn text(&self) -> String {
+        // rounded up so a new timer starts at its full time
+        let secs = (self.remaining().as_millis() as u64 + 999) / 1000;
+        if secs >= 3600 {
+            format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+        } else {
+            format!("{}:{:02}", secs / 60, secs % 60)
+        }
+    }
+
+    pub fn render(&self, state: &RenderState, frame: &mut Frame) {
+        if let Some(finished) = self.finished {
+            draw_flash(
+                state,
+                frame,
+                finished.elapsed(),
+                &self.name.to_uppercase(),
+                &Color::from_rgb8(255, 0, 0),
+            );
+            return;
+        }
+
+        let icon = if state.icons.get(&self.name).is_some() {
+            self.name.as_str()
+        } else {
+            "timer"
+        };
+        draw_scroll_text(
+            state,
+            frame,
+            self.created.elapsed(),
+            Some(icon),
+            &self.text(),
+            &state.color,
+        );
+        draw_progress(state, frame, Some(icon), self.progress(), &state.color);
+    }
+}
+
+fn default_preempt() -> u64 {
+    10
+}
+
+fn default_alarm() -> u64 {
+    10
+}
+
+#[derive(Clone, Deserialize)]
+pub struct TimerConfig {
+    // seconds before the end a timer takes over the screen
+    #[serde(default = "default_preempt")]
+    pub preempt: u64,
+    // seconds the alarm flashes
+    #[serde(default = "default_alarm")]
+    pub alarm: u64,
+}
+
+impl Default for TimerConfig {
+    fn default() -> TimerConfig {
+        TimerConfig {
+            preempt: default_preempt(),
+            alarm: default_alarm(),
+        }
+    }
+}
+
+pub struct Timers {
+    timers: Vec<Timer>,
+    config: TimerConfig,
+}
+
+impl Timers {
+    pub fn new(config: TimerConfig) -> Timers {
+        Timers {
+            timers: Vec::new(),
+            config,
+        }
+    }
+
+    pub fn command(&mut self, command: Command) -> Result<(), String> {
+        let idx = self
+            .timers
+            .iter()
+            .position(|timer| timer.name == command.name);
+        match (command.action, idx) {
+            (Action::Start, _) => {
+                let duration = command
+                    .duration()?
+                    .ok_or_else(|| "missing duration".to_string())?;
+                if let Some(idx) = idx {
+                    self.timers.remove(idx);
+                }
+                self.timers.push(Timer::new(command.name, duration));
+            }
+            (Action::Pause, Some(idx)) => self.timers[idx].pause(),
+            (Action::Resume, Some(idx)) => self.timers[idx].resume(),
+            (Action::Cancel, Some(idx)) => {
+                self.timers.remove(idx);
+            }
+            (_, None) => return Err(format!("unknown timer '{}'", command.name)),
+        }
+        Ok(())
+    }
+
+    // marks the timers that ran out and removes the ones done alarming, returns the finished names
+    pub fn update(&mut self) -> Vec<String> {
+        let alarm = Duration::from_secs(self.config.alarm);
+        self.timers.retain(|timer| match timer.finished {
+            Some(finished) => finished.elapsed() < alarm,
+            None => true,
+        });
+
+        let mut finished = Vec::new();
+        for timer in self.timers.iter_mut() {
+            if timer.finished.is_none() && timer.remaining() == Duration::from_secs(0) {
+                timer.finished = Some(Instant::now());
+                finished.push(timer.name.clone());
+            }
+        }
+        finished
+    }
+
+    // an alarming timer, or a running one close to its end
+    pub fn preempting(&self) -> Option<&Timer> {
+        let preempt = Duration::from_secs(self.config.preempt);
+        self.timers
+            .iter()
+            .find(|timer| timer.finished.is_some())
+            .or_else(|| {
+                self.timers
+                    .iter()
+                    .filter(|timer| timer.is_running() && timer.remaining() <= preempt)
+                    .min_by_key(|timer| timer.remaining())
+            })
+    }
+
+    // the timer that ends first
+    pub fn next_due(&self) -> Option<&Timer> {
+        self.timers
+            .iter()
+            .filter(|timer| timer.finished.is_none())
+            .min_by_key(|timer| timer.remaining())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(value: &str) -> Result<u64, String> {
+        parse_duration(value).map(|duration| duration.as_secs())
+    }
+
+    #[test]
+    fn parses_units() {
+        assert_eq!(secs("90"), Ok(90));
+        assert_eq!(secs("90s"), Ok(90));
+        assert_eq!(secs("9m"), Ok(540));
+        assert_eq!(secs("1h30m"), Ok(5400));
+        assert_eq!(secs(" 1m30 "), Ok(90));
+    }
+
+    #[test]
+    fn parses_minutes_and_seconds() {
+        assert_eq!(secs("1:30"), Ok(90));
+        assert_eq!(secs("0:05"), Ok(5));
+        assert_eq!(secs("1:00:00"), Ok(3600));
+    }
+
+    #[test]
+    fn rejects_invalid() {
+        assert!(secs("").is_err());
+        assert!(secs("m").is_err());
+        assert!(secs("9x").is_err());
+        assert!(secs("1:xx").is_err());
+        assert!(secs("-5").is_err());
+    }
+
+    #[test]
+    fn rejects_zero() {
+        assert!(secs("0").is_err());
+        assert!(secs("0m").is_err());
+        assert!(secs("0:00").is_err());
+        let command = Command::parse(r#"{"duration": 0}"#).unwrap();
+        assert!(command.duration().is_err());
+        let command = Command::parse(r#"{"duration": 5}"#).unwrap();
+        assert_eq!(command.duration(), Ok(Some(Duration::from_secs(5))));
+    }
+
+    #[test]
+    fn caps_the_duration() {
+        let duration = |value: &str| Command::parse(value).unwrap().duration();
+        assert_eq!(
+            duration(r#"{"duration": 360000}"#),
+            Ok(Some(Duration::from_secs(360000)))
+        );
+        assert!(duration(r#"{"duration": 360001}"#).is_err());
+        assert!(duration(r#"{"duration": 18446744073709551615}"#).is_err());
+        assert!(duration(r#"{"duration": "100h1s"}"#).is_err());
+        assert!(duration("pasta 101h").is_err());
+    }
+
+    #[test]
+    fn rejects_overflow() {
+        assert!(secs("9999999999999999h").is_err());
+        assert!(secs("18446744073709551615s1s").is_err());
+        assert!(secs("99999999999999999999").is_err());
+        assert!(secs("9999999999999999999:00").is_err());
+    }
+}